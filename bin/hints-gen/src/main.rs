@@ -2,7 +2,9 @@ use anyhow::Result;
 use clap::Parser;
 use tracing_subscriber::EnvFilter;
 
-use host::hints_gen::{run, HintsGenArgs};
+use host::hints_gen::{
+    is_timeout, run, run_command, HintsCommand, HintsGenArgs, TIMEOUT_EXIT_CODE,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "hints-gen")]
@@ -10,7 +12,11 @@ use host::hints_gen::{run, HintsGenArgs};
     about = "Generate ZisK hints from Ethereum block input files",
     long_about = "Runs block validation natively and captures hints for the ZisK prover.\n\nMust be compiled with: RUSTFLAGS=\"--cfg zisk_hints\" cargo build -p hints-gen"
 )]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<HintsCommand>,
+
     #[command(flatten)]
     args: HintsGenArgs,
 }
@@ -23,5 +29,15 @@ fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
-    run(cli.args)
+    let result = match cli.command {
+        Some(command) => run_command(command),
+        None => run(cli.args),
+    };
+    if let Err(e) = &result {
+        if is_timeout(e) {
+            eprintln!("Error: {e:?}");
+            std::process::exit(TIMEOUT_EXIT_CODE);
+        }
+    }
+    result
 }
@@ -1,7 +1,60 @@
+use std::process::Command;
+
 use zisk_sdk::build_program;
 
+/// Rust guests: cargo-zisk builds them and sets ZISK_ELF_<name> / ZISK_ELF_HASH_<name>.
+const GUESTS: [&str; 2] = [
+    "../guests/stateless-validator-reth",
+    "../guests/stateless-validator-ethrex",
+];
+
 fn main() {
-    // Rust guests: cargo-zisk builds them and sets ZISK_ELF_<name> / ZISK_ELF_HASH_<name>.
-    build_program("../guests/stateless-validator-reth");
-    build_program("../guests/stateless-validator-ethrex");
+    // Any rerun-if-changed line turns off Cargo's default of rerunning on a
+    // change anywhere in this package, so the guests' sources, and the
+    // workspace crates they build from, are listed explicitly rather than
+    // relying on build_program to emit them.
+    for guest in GUESTS {
+        build_program(guest);
+        println!("cargo:rerun-if-changed={guest}/src");
+        println!("cargo:rerun-if-changed={guest}/Cargo.toml");
+        println!("cargo:rerun-if-changed={guest}/Cargo.lock");
+    }
+    println!("cargo:rerun-if-changed=../../crates");
+
+    // Recorded in each run's metadata.json (see `src/metadata.rs`).
+    println!(
+        "cargo:rustc-env=ZEC_GIT_COMMIT={}",
+        git_commit().unwrap_or_default()
+    );
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../.git/refs");
+    println!(
+        "cargo:rustc-env=ZEC_ZISK_SDK_VERSION={}",
+        locked_version("zisk-sdk").unwrap_or_default()
+    );
+    println!("cargo:rerun-if-changed=../../Cargo.lock");
+}
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Version of `package` in the workspace lockfile.
+fn locked_version(package: &str) -> Option<String> {
+    let lock = std::fs::read_to_string("../../Cargo.lock").ok()?;
+    let name = format!("name = \"{package}\"");
+    let mut lines = lock.lines();
+    lines.find(|line| *line == name)?;
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(str::to_string)
 }
@@ -2,15 +2,33 @@ use anyhow::{Context, Result};
 use std::{
     fs,
     path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
 };
-use tracing::{error, info};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore, mpsc},
+    task::JoinSet,
+};
+use tracing::{info, warn};
 
-use input::{Client, ExecutionClient, create_client, generate_hints_to_file};
-use zisk_sdk::{GuestProgram, ZiskStdin};
+use input::{Client, ExecutionClient, InputMetadata, create_client, generate_hints_to_file};
+use zisk_sdk::ZiskStdin;
 
 use crate::{
     cli::Action,
-    zisk::{ZiskClient, ZiskExecutionMetrics},
+    elfs::guest_program,
+    failure::{Failure, FailureKind},
+    hints_cache::HintsCache,
+    hints_gen,
+    inputs::{self, InputFilter},
+    limits::{LimitExceeded, Limits},
+    report::{InputSummary, RunReport},
+    stream::{self, HintStream, StreamTimings},
+    system,
+    zisk::{HintsSource, ZiskClient, ZiskExecutionMetrics, format_hash},
 };
 
 pub struct BenchmarkRunner {
@@ -20,21 +38,257 @@ pub struct BenchmarkRunner {
     hints: Option<PathBuf>,
     gen_hints: bool,
     hints_out: Option<PathBuf>,
-    native_client: Option<Box<dyn ExecutionClient>>,
-    zisk_client: ZiskClient,
+    /// Where `--gen-hints` keeps hints instead of `hints_out` (`--hints-cache`).
+    hints_cache: Option<HintsCache>,
+    /// Stream each input's hints into its run over a socket (`--stream-hints`).
+    stream_hints: bool,
+    /// Generate each input's hints twice and compare them (`--check-determinism`).
+    check_determinism: bool,
+    /// Generates hints in `--gen-hints` mode and decodes the guest's public
+    /// outputs after proving.
+    native_client: Arc<dyn ExecutionClient>,
+    /// Creates the native client on the thread streaming hints.
+    client: Client,
+    /// One ZisK client per job: nothing in the SDK promises that a client can
+    /// run several executions or proofs at once.
+    zisk_clients: ClientPool,
+    jobs: usize,
+    /// `--steps-threshold` and `--timeout`, applied to every run.
+    limits: Limits,
+    /// Block hash every run must commit (`--expect-hash`), instead of the one
+    /// read from each input's block header.
+    expect_hash: Option<[u8; 32]>,
+}
+
+/// What one run consumes; see [`BenchmarkRunner::prepare_sources`].
+struct Sources {
+    input: Option<PathBuf>,
+    hints: Option<HintsSource>,
+    /// Block metadata decoded from the input, recorded in the result metrics.
+    /// `None` in hints mode (no input file) or when the input can't be decoded.
+    metadata: Option<InputMetadata>,
+    /// Block hash the guest must commit: `--expect-hash`, else the hash of the
+    /// block header carried by the input, when the client's format has one.
+    expected_hash: Option<[u8; 32]>,
+}
+
+/// A work item handed from the sources producer to the runs.
+struct WorkItem {
+    file: PathBuf,
+    current: usize,
+    work: Work,
+}
+
+enum Work {
+    /// The input already has a passing result, carried so the run summary
+    /// still covers the input.
+    Skip(InputSummary),
+    Run(Result<Sources>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Status {
+    #[default]
+    Passed,
+    Failed,
+    /// The run went over `--steps-threshold` or `--timeout`.
+    LimitExceeded,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct BenchmarkResult {
+    pub(crate) test_name: String,
+    /// Always `passed`; absent from results written before it was recorded.
+    #[serde(default)]
+    pub(crate) status: Status,
+    pub(crate) time: f64,
+    pub(crate) metrics: ZiskExecutionMetrics,
+    /// Prover-reported proving time, in seconds (`prove` only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) proving_time: Option<f64>,
+    /// Peak resident memory of the host process while proving, in bytes
+    /// (`prove` only, Linux only, not with `--jobs`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) peak_memory: Option<u64>,
+    /// Seconds from the start of hints generation to the end of the run
+    /// (`--stream-hints` only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) end_to_end_time: Option<f64>,
+    /// Native execution time of the streamed hints generation, in seconds
+    /// (`--stream-hints` only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) hints_generation_time: Option<f64>,
+}
+
+impl From<&BenchmarkResult> for InputSummary {
+    fn from(result: &BenchmarkResult) -> Self {
+        Self {
+            test_name: result.test_name.clone(),
+            chain: result.metrics.chain.clone(),
+            block_number: result.metrics.block_number,
+            time: result.time,
+            steps: result.metrics.steps,
+            cost: result.metrics.cost,
+            gas_used: result.metrics.gas_used,
+            tx_count: result.metrics.tx_count,
+        }
+    }
+}
+
+/// Result file of a failed run, written where its [`BenchmarkResult`] would go.
+#[derive(Debug, serde::Serialize)]
+struct FailedResult<'a> {
+    test_name: &'a str,
+    status: Status,
+    failure: &'a Failure,
+    /// Metrics of a run that finished over `--steps-threshold`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<&'a ZiskExecutionMetrics>,
 }
 
+/// Public outputs of a proof, written next to it as `<name>.publics.json`.
 #[derive(Debug, serde::Serialize)]
-struct BenchmarkResult {
-    test_name: String,
-    time: f64,
-    metrics: ZiskExecutionMetrics,
+struct ProofPublics {
+    committed_block_hash: Option<String>,
+    publics: String,
+}
+
+/// The guest committed a different block hash than `--expect-hash` (or the
+/// input's metadata) expected.
+#[derive(Debug)]
+pub struct BlockHashMismatch {
+    pub committed: [u8; 32],
+    pub expected: [u8; 32],
+}
+
+impl std::fmt::Display for BlockHashMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "guest committed block hash {}, expected {}",
+            format_hash(&self.committed),
+            format_hash(&self.expected)
+        )
+    }
+}
+
+impl std::error::Error for BlockHashMismatch {}
+
+/// Builds one of the runner's ZisK clients.
+type MakeClient = Box<dyn Fn() -> Result<ZiskClient> + Send + Sync>;
+
+/// The runner's ZisK clients, each lent to one run at a time.
+struct ClientPool {
+    clients: Arc<Mutex<Vec<ZiskClient>>>,
+    available: Arc<Semaphore>,
+    make: MakeClient,
+}
+
+/// A client lent by [`ClientPool::lend`], returned to the pool when dropped —
+/// unless the run using it was abandoned first (see [`ClientPool::replace`]).
+struct LentClient {
+    client: Option<ZiskClient>,
+    clients: Arc<Mutex<Vec<ZiskClient>>>,
+    permit: Option<OwnedSemaphorePermit>,
+    /// Set by whichever comes first: the client's return to the pool, or its
+    /// run being abandoned.
+    settled: Arc<AtomicBool>,
+}
+
+impl ClientPool {
+    fn new(jobs: usize, make: MakeClient) -> Result<Self> {
+        let clients = (0..jobs).map(|_| make()).collect::<Result<_>>()?;
+        Ok(Self {
+            available: Arc::new(Semaphore::new(jobs)),
+            clients: Arc::new(Mutex::new(clients)),
+            make,
+        })
+    }
+
+    async fn setup(&self) -> Result<()> {
+        let clients = std::mem::take(&mut *self.lock());
+        let mut ready = Vec::with_capacity(clients.len());
+        for client in clients {
+            client.setup().await?;
+            ready.push(client);
+        }
+        *self.lock() = ready;
+        Ok(())
+    }
+
+    /// Wait for a client no run is using.
+    async fn lend(&self) -> LentClient {
+        let permit = Arc::clone(&self.available)
+            .acquire_owned()
+            .await
+            .expect("the pool's semaphore is never closed");
+        let client = self
+            .lock()
+            .pop()
+            .expect("a permit is only available with a client in the pool");
+        LentClient {
+            client: Some(client),
+            clients: Arc::clone(&self.clients),
+            permit: Some(permit),
+            settled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Give up on the client whose run was abandoned, `settled` being its
+    /// [`LentClient::settled`], and set up a fresh one in its place so later
+    /// inputs don't wait for the abandoned run to return. The abandoned client
+    /// is dropped once its run does. Should the replacement fail, the abandoned
+    /// client keeps its slot and returns to the pool as before.
+    async fn replace(&self, settled: &AtomicBool) -> Result<()> {
+        let client = (self.make)().context("Failed to build a replacement ZisK client")?;
+        client
+            .setup()
+            .await
+            .context("Failed to set up a replacement ZisK client")?;
+        // A run that returned its client in the meantime keeps its slot.
+        if !settled.swap(true, Ordering::SeqCst) {
+            self.lock().push(client);
+            self.available.add_permits(1);
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ZiskClient>> {
+        self.clients.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl std::ops::Deref for LentClient {
+    type Target = ZiskClient;
+
+    fn deref(&self) -> &ZiskClient {
+        self.client.as_ref().expect("held until dropped")
+    }
+}
+
+impl Drop for LentClient {
+    fn drop(&mut self) {
+        let Some(client) = self.client.take() else {
+            return;
+        };
+        if self.settled.swap(true, Ordering::SeqCst) {
+            // Abandoned and replaced: the slot is the replacement's now.
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+            return;
+        }
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(client);
+    }
 }
 
 impl BenchmarkRunner {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        elf: GuestProgram,
         action: Action,
         output_folder: Option<PathBuf>,
         force_rerun: bool,
@@ -45,44 +299,54 @@ impl BenchmarkRunner {
         hints: Option<PathBuf>,
         gen_hints: bool,
         hints_out: Option<PathBuf>,
+        hints_cache: Option<PathBuf>,
+        stream_hints: bool,
+        check_determinism: bool,
         client: Client,
+        jobs: usize,
+        limits: Limits,
+        expect_hash: Option<[u8; 32]>,
     ) -> Result<Self> {
-        let use_hints = hints.is_some() || gen_hints;
-        let zisk_client = match &action {
-            Action::Execute => {
-                ZiskClient::for_execution(elf, emulator, unlock_mapped_memory, use_hints)?
+        let use_hints = hints.is_some() || gen_hints || stream_hints;
+        let jobs = jobs.max(1);
+        let zisk_client = move || {
+            let elf = guest_program(client);
+            match action {
+                Action::Execute => {
+                    ZiskClient::for_execution(elf, emulator, unlock_mapped_memory, use_hints)
+                }
+                Action::VerifyConstraints => ZiskClient::for_proving(
+                    elf,
+                    proving_key.clone(),
+                    emulator,
+                    unlock_mapped_memory,
+                    gpu,
+                    true,
+                    use_hints,
+                ),
+                Action::Prove => ZiskClient::for_proving(
+                    elf,
+                    proving_key.clone(),
+                    emulator,
+                    unlock_mapped_memory,
+                    gpu,
+                    false,
+                    use_hints,
+                ),
             }
-            Action::VerifyConstraints => ZiskClient::for_proving(
-                elf,
-                proving_key,
-                emulator,
-                unlock_mapped_memory,
-                gpu,
-                true,
-                use_hints,
-            )?,
-            Action::Prove => ZiskClient::for_proving(
-                elf,
-                proving_key,
-                emulator,
-                unlock_mapped_memory,
-                gpu,
-                false,
-                use_hints,
-            )?,
         };
+        let zisk_clients = ClientPool::new(jobs, Box::new(zisk_client))?;
 
-        // The native client (reth/ethrex) generates hints in --gen-hints mode.
-        let native_client = if gen_hints {
-            Some(create_client(client))
-        } else {
-            None
-        };
+        let native_client: Arc<dyn ExecutionClient> = create_client(client).into();
 
+        let hints_cache = hints_cache
+            .filter(|_| gen_hints)
+            .map(|root| HintsCache::new(&root, client))
+            .transpose()?;
         // Default the hints output dir to <client>-hints, mirroring hints-gen.
-        let hints_out = native_client
-            .as_ref()
-            .map(|nc| hints_out.unwrap_or_else(|| PathBuf::from(format!("{}-hints", nc.name()))));
+        let hints_out = (gen_hints && hints_cache.is_none()).then(|| {
+            hints_out.unwrap_or_else(|| PathBuf::from(format!("{}-hints", native_client.name())))
+        });
 
         Ok(Self {
             action,
@@ -91,39 +355,58 @@ impl BenchmarkRunner {
             hints,
             gen_hints,
             hints_out,
+            hints_cache,
+            stream_hints,
+            check_determinism,
             native_client,
-            zisk_client,
+            client,
+            zisk_clients,
+            jobs,
+            limits,
+            expect_hash,
         })
     }
 
-    pub async fn run(
-        &self,
-        input_folder: Option<&Path>,
-        include: Option<&[String]>,
-        exclude: Option<&[String]>,
-    ) -> Result<()> {
+    pub async fn run(self, input_folder: Option<&Path>, filter: &InputFilter) -> Result<()> {
         #[cfg(not(zisk_hints))]
-        if self.gen_hints {
-            anyhow::bail!("--gen-hints requires building with RUSTFLAGS=\"--cfg zisk_hints\"");
+        if self.gen_hints || self.stream_hints {
+            anyhow::bail!(
+                "--gen-hints and --stream-hints require building with RUSTFLAGS=\"--cfg zisk_hints\""
+            );
         }
 
-        if (self.hints.is_some() || self.gen_hints) && matches!(self.action, Action::Prove) {
+        if (self.hints.is_some() || self.gen_hints || self.stream_hints)
+            && matches!(self.action, Action::Prove)
+        {
             anyhow::bail!(
                 "hints are only supported with the execute and verify-constraints actions"
             );
         }
 
-        let mut files = if let Some(hints_root) = &self.hints {
+        if self.stream_hints && self.jobs > 1 {
+            anyhow::bail!(
+                "--stream-hints runs one input at a time (hints generation is process-global); \
+                 drop --jobs"
+            );
+        }
+
+        let files = if let Some(hints_root) = &self.hints {
             info!("Running with hints from {}", hints_root.display());
-            collect_files(hints_root)?
+            inputs::discover(hints_root, "hints", filter)?
         } else {
             let input_folder =
                 input_folder.ok_or_else(|| anyhow::anyhow!("No input folder provided"))?;
-            if self.gen_hints {
+            if let Some(cache) = &self.hints_cache {
+                info!(
+                    "Generating hints from {} into cache {}",
+                    input_folder.display(),
+                    cache.dir().display()
+                );
+            } else if self.gen_hints {
                 let out = self
                     .hints_out
                     .as_ref()
-                    .expect("hints_out is set whenever gen_hints is set");
+                    .expect("hints_out is set whenever gen_hints is set without a cache");
                 std::fs::create_dir_all(out).with_context(|| {
                     format!("Failed to create hints output dir: {}", out.display())
                 })?;
@@ -133,72 +416,200 @@ impl BenchmarkRunner {
                     out.display()
                 );
             }
-            collect_files(input_folder)?
+            let files = inputs::discover(input_folder, "bin", filter)?;
+            if self.gen_hints && self.hints_cache.is_none() {
+                inputs::check_unique_stems(&files)?;
+            }
+            files
         };
 
-        if let Some(patterns) = include {
-            info!("Include patterns: {:?}", patterns);
-            files.retain(|file| {
-                let name = file.to_string_lossy();
-                patterns.iter().any(|p| name.contains(p))
-            });
-        }
+        let total = files.len();
+        info!("Found {} files to run", total);
 
-        if let Some(patterns) = exclude {
-            info!("Exclude patterns: {:?}", patterns);
-            files.retain(|file| {
-                let name = file.to_string_lossy();
-                !patterns.iter().any(|p| name.contains(p))
-            });
+        self.zisk_clients.setup().await?;
+
+        let jobs = self.jobs;
+        if jobs > 1 {
+            info!("Running up to {} inputs concurrently", jobs);
         }
+        let runner = Arc::new(self);
 
-        let total = files.len();
-        info!("Found {} files to run", total);
+        // Hints generation is process-global (one native input, one hints
+        // sink), so a single producer prepares sources in order on a blocking
+        // thread. The bounded channel lets it run at most `jobs` items ahead of
+        // the runs consuming them.
+        let (tx, mut rx) = mpsc::channel(jobs);
+        let producer = {
+            let runner = Arc::clone(&runner);
+            tokio::task::spawn_blocking(move || {
+                for (index, file) in files.into_iter().enumerate() {
+                    let current = index + 1;
+                    let work = match runner.passed_result(&file) {
+                        Some(previous) => {
+                            info!("[{}/{}] Skipping {}", current, total, test_name(&file));
+                            Work::Skip(InputSummary::from(&previous))
+                        }
+                        None => Work::Run(runner.prepare_sources(&file)),
+                    };
+                    let item = WorkItem {
+                        file,
+                        current,
+                        work,
+                    };
+                    if tx.blocking_send(item).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+
+        let mut report = RunReport::default();
+        let mut tasks = JoinSet::new();
+        while let Some(item) = rx.recv().await {
+            let sources = match item.work {
+                Work::Skip(previous) => {
+                    report.record_skipped(test_name(&item.file), previous);
+                    continue;
+                }
+                Work::Run(Err(e)) => {
+                    let failure = runner.record_failure(&item.file, &e);
+                    report.record_failed(test_name(&item.file), failure);
+                    continue;
+                }
+                Work::Run(Ok(sources)) => sources,
+            };
 
-        self.zisk_client.setup().await?;
-
-        let mut passed = 0;
-        let mut failed = 0;
-        let mut skipped = 0;
-        for (index, file) in files.iter().enumerate() {
-            match self.run_single(file, index + 1, total).await {
-                Ok(true) => passed += 1,
-                Ok(false) => skipped += 1,
-                Err(e) => {
-                    error!("Failed to run benchmark for {}: {}", file.display(), e);
-                    failed += 1;
+            while tasks.len() >= jobs {
+                if let Some(joined) = tasks.join_next().await {
+                    record_joined(&mut report, joined);
                 }
             }
+
+            let runner = Arc::clone(&runner);
+            tasks.spawn(async move {
+                let result = runner
+                    .run_single(&item.file, sources, item.current, total)
+                    .await
+                    .map_err(|e| runner.record_failure(&item.file, &e));
+                (item.file, result)
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            record_joined(&mut report, joined);
         }
+        producer.await.context("Sources producer panicked")?;
 
         info!("");
         info!(
-            "Summary: {} passed, {} failed, {} skipped",
-            passed, failed, skipped
+            "Summary: {} passed, {} failed, {} limit exceeded, {} skipped",
+            report.passed(),
+            report.failed(),
+            report.limit_exceeded(),
+            report.skipped()
         );
 
+        let failed = report.failed();
+        let limit_exceeded = report.limit_exceeded();
+        if let Some(output_folder) = &runner.output_folder {
+            report.write(output_folder)?;
+        }
+
         // Propagate failures as a non-zero exit.
         if failed > 0 {
             anyhow::bail!("{failed} of {total} input(s) failed");
         }
+        if limit_exceeded > 0 {
+            anyhow::bail!("{limit_exceeded} of {total} input(s) exceeded a limit");
+        }
 
         Ok(())
     }
 
-    /// Resolve the `(input, hints)` sources for a work item, generating hints
+    /// Whether the action writes a result file per input (verify-constraints
+    /// writes none).
+    fn writes_results(&self) -> bool {
+        matches!(self.action, Action::Execute | Action::Prove)
+    }
+
+    /// The passing result an earlier run left for `work_file`, if any; such
+    /// inputs are skipped unless `--force-rerun`. Failed results are rerun.
+    fn passed_result(&self, work_file: &Path) -> Option<BenchmarkResult> {
+        if self.force_rerun || !self.writes_results() {
+            return None;
+        }
+        read_passed_result(&self.result_path(work_file)?)
+    }
+
+    /// Classify a failed run and write it as the input's result, so the failure
+    /// category is on record next to the passing results. A run over a limit is
+    /// written as `limit_exceeded`, with its metrics when it finished.
+    fn record_failure(&self, work_file: &Path, error: &anyhow::Error) -> Failure {
+        let failure = Failure::from_error(error);
+        if self.writes_results()
+            && let Some(output_file) = self.result_path(work_file)
+        {
+            let exceeded = error.downcast_ref::<LimitExceeded>();
+            let result = FailedResult {
+                test_name: test_name(work_file),
+                status: match exceeded {
+                    Some(_) => Status::LimitExceeded,
+                    None => Status::Failed,
+                },
+                failure: &failure,
+                metrics: exceeded.and_then(|e| e.metrics.as_ref()),
+            };
+            if let Err(e) = self.write_result(&output_file, &result) {
+                warn!("Could not write {}: {}", output_file.display(), e);
+            }
+        }
+        failure
+    }
+
+    /// Resolve the input and hints sources for a work item, generating hints
     /// first in `--gen-hints` mode:
     /// - hints mode: the work file IS the hints (input carried by the hints).
-    /// - gen-hints mode: the work file is an input; generate its hints natively,
-    ///   then run with those (empty stdin).
+    /// - stream-hints mode: the work file is an input; its hints are generated
+    ///   into a socket as the run starts, and the run reads them from there.
+    /// - gen-hints mode: the work file is an input; generate its hints natively
+    ///   (or find them in `--hints-cache`), then run with those (empty stdin).
     /// - otherwise: the work file is an input, run normally.
-    fn prepare_sources(&self, work_file: &Path) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
+    fn prepare_sources(&self, work_file: &Path) -> Result<Sources> {
         if self.hints.is_some() {
-            Ok((None, Some(work_file.to_path_buf())))
+            return Ok(Sources {
+                input: None,
+                hints: Some(HintsSource::File(work_file.to_path_buf())),
+                metadata: None,
+                expected_hash: self.expect_hash,
+            });
+        }
+
+        let (metadata, input_hash) = self.decode_input(work_file);
+        let expected_hash = self.expect_hash.or(input_hash);
+        if self.stream_hints {
+            // The hints are generated when the run starts; see `run_single`.
+            Ok(Sources {
+                input: None,
+                hints: Some(HintsSource::Socket(stream::socket_path())),
+                metadata,
+                expected_hash,
+            })
+        } else if let Some(cache) = &self.hints_cache {
+            let stdin = ZiskStdin::from_file(work_file).context("Failed to load input file")?;
+            let cached = cache.get_or_generate(
+                work_file,
+                &stdin,
+                &self.native_client,
+                self.check_determinism,
+                None,
+            )?;
+            Ok(Sources {
+                input: None,
+                hints: Some(HintsSource::File(cached.path)),
+                metadata,
+                expected_hash,
+            })
         } else if self.gen_hints {
-            let client = self
-                .native_client
-                .as_deref()
-                .ok_or_else(|| anyhow::anyhow!("native client not initialized"))?;
+            let client = &self.native_client;
             let out = self
                 .hints_out
                 .as_ref()
@@ -209,61 +620,199 @@ impl BenchmarkRunner {
             let hints_path = out.join(stem).with_extension("hints");
 
             let stdin = ZiskStdin::from_file(work_file).context("Failed to load input file")?;
-            generate_hints_to_file(&stdin, hints_path.clone(), client)?;
+            generate_hints_to_file(&stdin, hints_path.clone(), None, client)?;
+            if self.check_determinism {
+                hints_gen::check_determinism(&stdin, &hints_path, None, client)?;
+            }
 
-            Ok((None, Some(hints_path)))
+            Ok(Sources {
+                input: None,
+                hints: Some(HintsSource::File(hints_path)),
+                metadata,
+                expected_hash,
+            })
         } else {
-            Ok((Some(work_file.to_path_buf()), None))
+            Ok(Sources {
+                input: Some(work_file.to_path_buf()),
+                hints: None,
+                metadata,
+                expected_hash,
+            })
         }
     }
 
-    async fn run_single(&self, work_file: &Path, current: usize, total: usize) -> Result<bool> {
-        let test_name = work_file
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown");
+    /// Decode the block metadata and block hash of an input file. Failures
+    /// only cost the result its block fields and the committed-hash check, so
+    /// they are logged rather than returned.
+    fn decode_input(&self, input_file: &Path) -> (Option<InputMetadata>, Option<[u8; 32]>) {
+        let bytes = match fs::read(input_file) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Could not read {}: {}", input_file.display(), e);
+                return (None, None);
+            }
+        };
+        let metadata = self
+            .native_client
+            .input_metadata(&bytes)
+            .inspect_err(|e| {
+                warn!(
+                    "Could not decode block metadata from {}: {e:#}",
+                    test_name(input_file)
+                )
+            })
+            .ok();
+        // Not every input format carries the block header (ziskethone's doesn't).
+        let hash = self.native_client.input_block_hash(&bytes).ok();
+        (metadata, hash)
+    }
 
-        match &self.action {
-            Action::Execute => {
-                if let Some(ref output_folder) = self.output_folder {
-                    let filename = work_file.file_name().unwrap_or_default();
-                    let output_file = output_folder.join(filename).with_extension("json");
+    /// Record the block hash the guest committed in `metrics`, failing the run
+    /// when it differs from `expected`.
+    fn check_committed(
+        &self,
+        metrics: &mut ZiskExecutionMetrics,
+        publics: &[u8],
+        expected: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let committed = match self.native_client.committed_block_hash(publics) {
+            Ok(hash) => hash,
+            Err(e) if expected.is_some() => {
+                return Err(e.context("Failed to decode the committed block hash"));
+            }
+            Err(e) => {
+                warn!("Could not decode the committed block hash: {e:#}");
+                return Ok(());
+            }
+        };
+        let committed_hex = format_hash(&committed);
+        info!("Committed block hash: {}", committed_hex);
+        metrics.committed_block_hash = Some(committed_hex);
 
-                    if output_file.exists() && !self.force_rerun {
-                        info!("[{}/{}] Skipping {}", current, total, test_name);
-                        return Ok(false);
+        if let Some(expected) = expected
+            && committed != expected
+        {
+            return Err(BlockHashMismatch {
+                committed,
+                expected,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Where the JSON result for `work_file` goes, when an output folder is set.
+    fn result_path(&self, work_file: &Path) -> Option<PathBuf> {
+        let output_folder = self.output_folder.as_ref()?;
+        let filename = work_file.file_name().unwrap_or_default();
+        Some(output_folder.join(filename).with_extension("json"))
+    }
+
+    fn write_result(&self, output_file: &Path, result: &impl serde::Serialize) -> Result<()> {
+        if let Some(parent) = output_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let output_json = serde_json::to_string_pretty(result)?;
+        fs::write(output_file, output_json)?;
+        Ok(())
+    }
+
+    /// Start generating `work_file`'s hints when they are to be streamed.
+    async fn start_stream(
+        &self,
+        work_file: &Path,
+        hints: Option<&HintsSource>,
+    ) -> Result<Option<HintStream>> {
+        match hints {
+            Some(HintsSource::Socket(socket)) => Ok(Some(
+                HintStream::start(self.client, work_file, socket.clone()).await?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Run a ZisK call on a client of its own, under `--timeout`. The call
+    /// gets a blocking thread of its own so the timeout fires even while the
+    /// executor holds it; a call that times out can't be cancelled and is left
+    /// to finish in the background with its client, whose slot goes to a fresh
+    /// one.
+    async fn run_limited<T, F>(&self, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ZiskClient) -> Result<T> + Send + 'static,
+    {
+        let zisk = self.zisk_clients.lend().await;
+        let settled = Arc::clone(&zisk.settled);
+        let call = tokio::task::spawn_blocking(move || call(&zisk));
+        let joined = match self.limits.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                Ok(joined) => joined,
+                Err(_) => {
+                    if let Err(e) = self.zisk_clients.replace(&settled).await {
+                        warn!("{:#}; later runs wait for the abandoned one", e);
                     }
+                    return Err(LimitExceeded::timeout(timeout).into());
                 }
+            },
+            None => call.await,
+        };
+        joined.context("ZisK run panicked")?
+    }
 
+    async fn run_single(
+        self: &Arc<Self>,
+        work_file: &Path,
+        sources: Sources,
+        current: usize,
+        total: usize,
+    ) -> Result<InputSummary> {
+        let test_name = test_name(work_file);
+        let Sources {
+            input: input_file,
+            hints,
+            metadata,
+            expected_hash,
+        } = sources;
+        let tag = |metrics: &mut ZiskExecutionMetrics| {
+            if let Some(metadata) = &metadata {
+                metrics.set_input_metadata(metadata);
+            }
+        };
+
+        let result = match &self.action {
+            Action::Execute => {
                 info!("[{}/{}] Running: {}", current, total, test_name);
 
-                let (input_file, hints_file) = self.prepare_sources(work_file)?;
-                let metrics = self
-                    .zisk_client
-                    .execute(input_file.as_deref(), hints_file.as_deref())
+                let stream = self.start_stream(work_file, hints.as_ref()).await?;
+                let output = self
+                    .run_limited(move |zisk| {
+                        block_on(zisk.execute(input_file.as_deref(), hints.as_ref()))
+                    })
                     .await?;
+                let streamed = finish_stream(stream).await?;
+                let mut metrics = output.metrics;
+                tag(&mut metrics);
                 let elapsed = metrics.duration.as_secs_f64();
 
                 info!("Execution metrics — {}", metrics);
+                self.check_committed(&mut metrics, &output.publics, expected_hash)?;
+                self.limits.check_steps(&metrics)?;
                 info!("[{}/{}] Completed in {:.2}s", current, total, elapsed);
 
-                if let Some(ref output_folder) = self.output_folder {
-                    let filename = work_file.file_name().unwrap_or_default();
-                    let output_file = output_folder.join(filename).with_extension("json");
-
-                    if let Some(parent) = output_file.parent() {
-                        fs::create_dir_all(parent)?;
-                    }
-
-                    let result = BenchmarkResult {
-                        test_name: test_name.to_string(),
-                        time: elapsed,
-                        metrics,
-                    };
-
-                    let output_json = serde_json::to_string_pretty(&result)?;
-                    fs::write(&output_file, output_json)?;
+                let result = BenchmarkResult {
+                    test_name: test_name.to_string(),
+                    status: Status::Passed,
+                    time: elapsed,
+                    metrics,
+                    proving_time: None,
+                    peak_memory: None,
+                    end_to_end_time: streamed.map(|t| t.end_to_end.as_secs_f64()),
+                    hints_generation_time: streamed.map(|t| t.generation.as_secs_f64()),
+                };
+                if let Some(output_file) = self.result_path(work_file) {
+                    self.write_result(&output_file, &result)?;
                 }
+                result
             }
 
             Action::VerifyConstraints => {
@@ -272,48 +821,230 @@ impl BenchmarkRunner {
                     current, total, test_name
                 );
 
-                let (input_file, hints_file) = self.prepare_sources(work_file)?;
-                let metrics = self
-                    .zisk_client
-                    .verify_constraints(input_file.as_deref(), hints_file.as_deref())
+                let stream = self.start_stream(work_file, hints.as_ref()).await?;
+                let mut metrics = self
+                    .run_limited(move |zisk| {
+                        block_on(zisk.verify_constraints(input_file.as_deref(), hints.as_ref()))
+                    })
                     .await?;
+                let streamed = finish_stream(stream).await?;
+                tag(&mut metrics);
+                self.limits.check_steps(&metrics)?;
                 let elapsed = metrics.duration.as_secs_f64();
 
                 info!("[{}/{}] PASSED in {:.2}s", current, total, elapsed);
+
+                BenchmarkResult {
+                    test_name: test_name.to_string(),
+                    status: Status::Passed,
+                    time: elapsed,
+                    metrics,
+                    proving_time: None,
+                    peak_memory: None,
+                    end_to_end_time: streamed.map(|t| t.end_to_end.as_secs_f64()),
+                    hints_generation_time: streamed.map(|t| t.generation.as_secs_f64()),
+                }
             }
 
             Action::Prove => {
-                unimplemented!("Prove action is not implemented yet");
+                let output_file = self.result_path(work_file);
+
+                info!("[{}/{}] Proving: {}", current, total, test_name);
+
+                // The proof and its public outputs sit next to the JSON result.
+                let proof_path = output_file.as_ref().map(|f| f.with_extension("proof"));
+                if let Some(parent) = proof_path.as_ref().and_then(|p| p.parent()) {
+                    fs::create_dir_all(parent)?;
+                }
+                if proof_path.is_none() {
+                    warn!("No --output-folder set; the proof will not be saved");
+                }
+
+                // The peak is process-wide, so it only means this proof's when
+                // no other run is in flight.
+                let measure_memory = self.jobs == 1;
+                if measure_memory {
+                    system::reset_peak_rss();
+                }
+                let start = Instant::now();
+                let mut output = self
+                    .run_limited(move |zisk| {
+                        block_on(zisk.prove(
+                            input_file.as_deref(),
+                            hints.as_ref(),
+                            proof_path.as_deref(),
+                        ))
+                    })
+                    .await?;
+                let elapsed = start.elapsed().as_secs_f64();
+                let peak_memory = measure_memory.then(system::peak_rss_bytes).flatten();
+                tag(&mut output.metrics);
+                let proving_time = output.metrics.duration.as_secs_f64();
+
+                info!("Proving metrics — {}", output.metrics);
+                let checked =
+                    self.check_committed(&mut output.metrics, &output.publics, expected_hash);
+
+                // The publics are written even when the check failed, to
+                // inspect what the guest committed.
+                if let Some(output_file) = &output_file {
+                    let publics = ProofPublics {
+                        committed_block_hash: output.metrics.committed_block_hash.clone(),
+                        publics: output.publics.iter().map(|b| format!("{b:02x}")).collect(),
+                    };
+                    fs::write(
+                        output_file.with_extension("publics.json"),
+                        serde_json::to_string_pretty(&publics)?,
+                    )?;
+                }
+                checked?;
+                self.limits.check_steps(&output.metrics)?;
+
+                info!(
+                    "[{}/{}] PROVED in {:.2}s (proving: {:.2}s)",
+                    current, total, elapsed, proving_time
+                );
+
+                let result = BenchmarkResult {
+                    test_name: test_name.to_string(),
+                    status: Status::Passed,
+                    time: elapsed,
+                    metrics: output.metrics,
+                    proving_time: Some(proving_time),
+                    peak_memory,
+                    end_to_end_time: None,
+                    hints_generation_time: None,
+                };
+                if let Some(output_file) = &output_file {
+                    self.write_result(output_file, &result)?;
+                }
+                result
             }
-        }
+        };
 
-        Ok(true)
+        Ok(InputSummary::from(&result))
     }
 }
 
-fn collect_files(root: &Path) -> Result<Vec<PathBuf>> {
-    let mut files = Vec::new();
-
-    if root.is_dir() {
-        for entry in fs::read_dir(root)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                for sub_entry in fs::read_dir(&path)? {
-                    let sub_entry = sub_entry?;
-                    let sub_path = sub_entry.path();
-                    if sub_path.is_file() {
-                        files.push(sub_path);
-                    }
-                }
-            } else if path.is_file() {
-                files.push(path);
-            }
+async fn finish_stream(stream: Option<HintStream>) -> Result<Option<StreamTimings>> {
+    match stream {
+        Some(stream) => Ok(Some(stream.finish().await?)),
+        None => Ok(None),
+    }
+}
+
+/// Drive a ZisK SDK future from the blocking thread [`run_limited`] gives it.
+///
+/// [`run_limited`]: BenchmarkRunner::run_limited
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Handle::current().block_on(future)
+}
+
+/// The result file at `path`, when it holds a passing result.
+fn read_passed_result(path: &Path) -> Option<BenchmarkResult> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<BenchmarkResult>(&contents)
+        .ok()
+        .filter(|result| result.status == Status::Passed)
+}
+
+fn test_name(work_file: &Path) -> &str {
+    work_file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+}
+
+fn record_joined(
+    report: &mut RunReport,
+    joined: Result<(PathBuf, Result<InputSummary, Failure>), tokio::task::JoinError>,
+) {
+    match joined {
+        Ok((_, Ok(summary))) => report.record_passed(summary),
+        Ok((file, Err(failure))) => report.record_failed(test_name(&file), failure),
+        Err(e) => report.record_failed(
+            "<panicked task>",
+            Failure {
+                kind: FailureKind::Other,
+                message: format!("Benchmark task panicked: {e}"),
+            },
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(steps: u64) -> ZiskExecutionMetrics {
+        ZiskExecutionMetrics {
+            duration: Duration::ZERO,
+            steps,
+            cost: Some(2 * steps),
+            tx_count: Some(3),
+            gas_used: Some(1_000),
+            chain: Some("mainnet".to_string()),
+            block_number: Some(7),
+            committed_block_hash: None,
         }
-    } else {
-        files.push(root.to_path_buf());
     }
 
-    files.sort();
-    Ok(files)
+    /// The skip path of `run`: an input whose earlier result passed is
+    /// recorded as skipped, keeping that result's numbers in the summary.
+    #[test]
+    fn skipped_input_keeps_its_numbers_in_the_summary() {
+        let dir = std::env::temp_dir().join(format!("benchmark-skip-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let passed = BenchmarkResult {
+            test_name: "passed".to_string(),
+            status: Status::Passed,
+            time: 1.5,
+            metrics: metrics(500),
+            proving_time: None,
+            peak_memory: None,
+            end_to_end_time: None,
+            hints_generation_time: None,
+        };
+        fs::write(
+            dir.join("passed.json"),
+            serde_json::to_string(&passed).unwrap(),
+        )
+        .unwrap();
+        let failed = FailedResult {
+            test_name: "failed",
+            status: Status::Failed,
+            failure: &Failure {
+                kind: FailureKind::Other,
+                message: "boom".to_string(),
+            },
+            metrics: None,
+        };
+        fs::write(
+            dir.join("failed.json"),
+            serde_json::to_string(&failed).unwrap(),
+        )
+        .unwrap();
+
+        // Failed results are rerun, not skipped.
+        assert!(read_passed_result(&dir.join("failed.json")).is_none());
+        assert!(read_passed_result(&dir.join("missing.json")).is_none());
+
+        let previous = read_passed_result(&dir.join("passed.json")).expect("passing result");
+        let mut report = RunReport::default();
+        report.record_skipped("passed", InputSummary::from(&previous));
+        assert_eq!(report.skipped(), 1);
+        assert_eq!(report.passed(), 0);
+
+        let out = dir.join("out");
+        report.write(&out).unwrap();
+        let summary: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(out.join("summary.json")).unwrap()).unwrap();
+        assert_eq!(summary["totals"]["inputs"], 1);
+        assert_eq!(summary["totals"]["skipped"], 1);
+        assert_eq!(summary["totals"]["steps"], 500);
+        assert_eq!(summary["inputs"][0]["test_name"], "passed");
+        assert_eq!(summary["inputs"][0]["block_number"], 7);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,6 +1,8 @@
 use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+use crate::inputs::InputFilter;
+
 /// ZisK Ethereum Client Host - Benchmark runner
 #[derive(Parser, Debug)]
 #[command(name = "zec-host")]
@@ -38,13 +40,28 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub gpu: bool,
 
+    /// Number of inputs to run concurrently
+    #[arg(short, long, default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Report a run that took more ZisK steps than this as limit exceeded.
+    /// Checked against the steps the run reports once it completes: the run
+    /// itself isn't capped
+    #[arg(long, value_name = "STEPS")]
+    pub steps_threshold: Option<u64>,
+
+    /// Abandon a run still going after this many seconds and report it as
+    /// limit exceeded
+    #[arg(long, value_name = "SECS")]
+    pub timeout: Option<u64>,
+
     /// Increase log verbosity (`-v` = debug, `-vv` = trace)
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
 }
 
 /// Actions to perform
-#[derive(Debug, Clone, ValueEnum, serde::Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Serialize, serde::Deserialize)]
 pub enum Action {
     /// Execute
     Execute,
@@ -54,7 +71,8 @@ pub enum Action {
     Prove,
 }
 
-/// Subcommands for different guest programs
+/// Subcommands: the guest programs to benchmark, plus offline tools over their
+/// results
 #[derive(Subcommand, Clone, Debug)]
 pub enum GuestProgramCommand {
     /// Ethereum Stateless Validator
@@ -77,25 +95,120 @@ pub enum GuestProgramCommand {
         #[arg(long, requires = "gen_hints")]
         hints_out: Option<PathBuf>,
 
+        /// With --gen-hints, keep hints in this content-addressed cache (keyed
+        /// by input bytes, client and guest ELF) and only generate missing ones
+        #[arg(long, requires = "gen_hints", conflicts_with = "hints_out")]
+        hints_cache: Option<PathBuf>,
+
+        /// Generate each input's hints while the run consumes them over a Unix
+        /// socket, without writing a hints file
+        #[arg(
+            long,
+            requires = "input_folder",
+            conflicts_with_all = ["hints", "gen_hints"]
+        )]
+        stream_hints: bool,
+
+        /// Generate each input's hints twice and fail the input if they differ
+        #[arg(long, requires = "gen_hints")]
+        check_determinism: bool,
+
         /// Client
         #[arg(short, long, default_value = "reth")]
         client: input::Client,
 
-        /// Include only files containing the provided strings.
-        #[arg(long)]
-        include: Option<Vec<String>>,
+        /// Which inputs (or hints) under the folder to run
+        #[command(flatten)]
+        filter: InputFilter,
 
-        /// Exclude files containing the provided strings.
+        /// Block hash every run must commit (0x-prefixed hex). By default each
+        /// run is checked against its input's block header, when it has one.
         #[arg(long)]
-        exclude: Option<Vec<String>>,
+        expect_hash: Option<String>,
     },
     // Add more guest programs here as needed
+    /// Verify a proof generated by `-a prove`, without re-running the prover
+    Verify {
+        /// Proof file (`<name>.proof` in the prove output folder)
+        #[arg(short, long)]
+        proof: PathBuf,
+
+        /// Client whose guest program produced the proof
+        #[arg(short, long, default_value = "reth")]
+        client: input::Client,
+
+        /// Block hash the proof must commit (0x-prefixed hex)
+        #[arg(long, conflicts_with = "input")]
+        expected_hash: Option<String>,
+
+        /// Input `.bin` the proof was generated from; its block hash is the
+        /// expected committed one
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+    },
+    /// Compare two benchmark output folders (baseline vs. candidate) by test
+    /// name; exits non-zero when more inputs regress than the budget allows
+    Compare {
+        /// Output folder of the baseline run
+        baseline: PathBuf,
+
+        /// Output folder of the candidate run
+        candidate: PathBuf,
+
+        /// Flag inputs whose steps or cost change by more than this percentage
+        #[arg(short, long, default_value_t = 1.0)]
+        threshold: f64,
+
+        /// Also flag inputs whose time changes by more than this percentage
+        /// (time is only reported by default: it is noisy across runs)
+        #[arg(long)]
+        time_threshold: Option<f64>,
+
+        /// Number of regressed inputs tolerated before failing
+        #[arg(short, long, default_value_t = 0)]
+        budget: usize,
+
+        /// Write the full comparison as JSON to this file
+        #[arg(long)]
+        json: Option<PathBuf>,
+
+        /// Compare even when the runs' metadata.json says they aren't
+        /// comparable (different action, client, ZisK version, ...)
+        #[arg(long)]
+        allow_incompatible: bool,
+    },
+    /// Execute one block under every client's guest program and compare the
+    /// committed block hashes; exits non-zero on a consensus divergence
+    Differential {
+        /// Input `.bin` of the block, for any client
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Clients to compare (comma-separated; default: all)
+        #[arg(short, long, value_delimiter = ',')]
+        clients: Vec<input::Client>,
+
+        /// Extra folders to search for the other clients' inputs
+        #[arg(long)]
+        inputs_dir: Vec<PathBuf>,
+
+        /// Generate missing inputs from this RPC endpoint
+        #[arg(long)]
+        rpc_url: Option<String>,
+
+        /// Write the report as JSON to this file
+        #[arg(long)]
+        json: Option<PathBuf>,
+    },
 }
 
 impl GuestProgramCommand {
     pub fn display_name(&self) -> String {
         match self {
             Self::StatelessValidator { .. } => "Stateless Validator".to_string(),
+            Self::Verify { .. } => "Verify Proof".to_string(),
+            Self::Compare { .. } => "Compare Runs".to_string(),
+            Self::Differential { .. } => "Differential Execution".to_string(),
         }
     }
 }
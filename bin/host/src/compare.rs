@@ -0,0 +1,554 @@
+//! `compare`: diff two benchmark output folders (a baseline and a candidate
+//! run over the same inputs), matching result files by test name.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{info, warn};
+
+use crate::{
+    benchmark::{BenchmarkResult, Status},
+    metadata::{self, RunMetadata},
+};
+
+/// Thresholds above which a change is flagged, in percent.
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// Applies to steps and cost.
+    pub threshold: f64,
+    /// Applies to wall time; `None` reports time deltas without flagging them,
+    /// since time is noisy across machines and runs.
+    pub time_threshold: Option<f64>,
+    /// Number of regressed inputs tolerated before the comparison fails.
+    pub budget: usize,
+    /// Compare runs whose metadata says they aren't comparable, with a warning.
+    pub allow_incompatible: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Regressed,
+    Improved,
+    Unchanged,
+}
+
+/// Baseline and candidate values of one metric, with the relative change.
+#[derive(Debug, Serialize)]
+pub struct Delta {
+    pub baseline: f64,
+    pub candidate: f64,
+    /// `(candidate - baseline) / baseline`, in percent; `None` when the
+    /// baseline is zero.
+    pub percent: Option<f64>,
+}
+
+impl Delta {
+    fn new(baseline: f64, candidate: f64) -> Self {
+        let percent = (baseline != 0.0).then(|| (candidate - baseline) / baseline * 100.0);
+        Self {
+            baseline,
+            candidate,
+            percent,
+        }
+    }
+
+    fn verdict(&self, threshold: f64) -> Verdict {
+        match self.percent {
+            Some(p) if p > threshold => Verdict::Regressed,
+            Some(p) if p < -threshold => Verdict::Improved,
+            // A metric appearing from zero is a regression whatever the threshold.
+            None if self.candidate > 0.0 => Verdict::Regressed,
+            _ => Verdict::Unchanged,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InputComparison {
+    pub test_name: String,
+    pub steps: Delta,
+    /// `None` unless both runs recorded a cost.
+    pub cost: Option<Delta>,
+    pub time: Delta,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub baseline: PathBuf,
+    pub candidate: PathBuf,
+    pub threshold: f64,
+    pub time_threshold: Option<f64>,
+    pub budget: usize,
+    /// Why the runs aren't comparable, when compared anyway
+    /// (`--allow-incompatible`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub incompatibilities: Vec<String>,
+    /// Sums over the inputs that passed in both runs.
+    pub totals: Totals,
+    /// Inputs whose numbers regressed, and those in `failed_in_candidate`.
+    pub regressed: Vec<String>,
+    pub improved: Vec<String>,
+    /// Inputs that passed in the baseline but failed, went over a limit or
+    /// have no result in the candidate.
+    pub failed_in_candidate: Vec<String>,
+    /// Inputs with a result in only one of the runs, other than those in
+    /// `failed_in_candidate`.
+    pub only_in_baseline: Vec<String>,
+    pub only_in_candidate: Vec<String>,
+    pub inputs: Vec<InputComparison>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Totals {
+    pub inputs: usize,
+    pub steps: Delta,
+    pub cost: Delta,
+    pub time: Delta,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.regressed.len() <= self.budget
+    }
+}
+
+/// The results of one run, by test name.
+#[derive(Debug, Default)]
+struct RunResults {
+    passed: BTreeMap<String, BenchmarkResult>,
+    /// Inputs whose result is `failed` or `limit_exceeded`.
+    not_passed: BTreeMap<String, Status>,
+}
+
+impl RunResults {
+    fn contains(&self, name: &str) -> bool {
+        self.passed.contains_key(name) || self.not_passed.contains_key(name)
+    }
+}
+
+/// Compare the results in `baseline` against those in `candidate`.
+pub fn compare(baseline: &Path, candidate: &Path, options: &CompareOptions) -> Result<Comparison> {
+    let incompatibilities = check_compatible(baseline, candidate, options)?;
+    let base = load_results(baseline)?;
+    let cand = load_results(candidate)?;
+    Ok(Comparison {
+        incompatibilities,
+        ..compare_results(baseline, candidate, base, cand, options)
+    })
+}
+
+fn compare_results(
+    baseline: &Path,
+    candidate: &Path,
+    base: RunResults,
+    cand: RunResults,
+    options: &CompareOptions,
+) -> Comparison {
+    // A candidate that stops passing an input regressed on it, however it
+    // failed; the baseline's own failures are only listed.
+    let failed_in_candidate: Vec<String> = base
+        .passed
+        .keys()
+        .filter(|name| !cand.passed.contains_key(*name))
+        .cloned()
+        .collect();
+    let only_in_baseline: Vec<String> = base
+        .not_passed
+        .keys()
+        .filter(|name| !cand.contains(name))
+        .cloned()
+        .collect();
+    let only_in_candidate: Vec<String> = cand
+        .passed
+        .keys()
+        .chain(cand.not_passed.keys())
+        .filter(|name| !base.contains(name))
+        .cloned()
+        .collect();
+    let RunResults {
+        passed: mut base, ..
+    } = base;
+    let RunResults {
+        passed: mut cand, ..
+    } = cand;
+    base.retain(|name, _| cand.contains_key(name));
+    cand.retain(|name, _| base.contains_key(name));
+
+    let mut inputs = Vec::with_capacity(base.len());
+    for (name, b) in &base {
+        let c = &cand[name];
+        let steps_delta = Delta::new(b.metrics.steps as f64, c.metrics.steps as f64);
+        let cost_delta = match (b.metrics.cost, c.metrics.cost) {
+            (Some(bc), Some(cc)) => Some(Delta::new(bc as f64, cc as f64)),
+            _ => None,
+        };
+        let time_delta = Delta::new(b.time, c.time);
+
+        let mut verdicts = vec![steps_delta.verdict(options.threshold)];
+        verdicts.extend(cost_delta.as_ref().map(|d| d.verdict(options.threshold)));
+        verdicts.extend(options.time_threshold.map(|t| time_delta.verdict(t)));
+        let verdict = if verdicts.contains(&Verdict::Regressed) {
+            Verdict::Regressed
+        } else if verdicts.contains(&Verdict::Improved) {
+            Verdict::Improved
+        } else {
+            Verdict::Unchanged
+        };
+
+        inputs.push(InputComparison {
+            test_name: name.clone(),
+            steps: steps_delta,
+            cost: cost_delta,
+            time: time_delta,
+            verdict,
+        });
+    }
+
+    let total = |delta: fn(&InputComparison) -> Option<&Delta>| {
+        let (baseline, candidate) = inputs
+            .iter()
+            .filter_map(delta)
+            .fold((0.0, 0.0), |(b, c), d| (b + d.baseline, c + d.candidate));
+        Delta::new(baseline, candidate)
+    };
+    let names_with = |verdict: Verdict| {
+        inputs
+            .iter()
+            .filter(|i| i.verdict == verdict)
+            .map(|i| i.test_name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let mut regressed = names_with(Verdict::Regressed);
+    regressed.extend(failed_in_candidate.iter().cloned());
+    regressed.sort();
+
+    Comparison {
+        baseline: baseline.to_path_buf(),
+        candidate: candidate.to_path_buf(),
+        threshold: options.threshold,
+        time_threshold: options.time_threshold,
+        budget: options.budget,
+        incompatibilities: Vec::new(),
+        totals: Totals {
+            inputs: inputs.len(),
+            steps: total(|i| Some(&i.steps)),
+            cost: total(|i| i.cost.as_ref()),
+            time: total(|i| Some(&i.time)),
+        },
+        regressed,
+        improved: names_with(Verdict::Improved),
+        failed_in_candidate,
+        only_in_baseline,
+        only_in_candidate,
+        inputs,
+    }
+}
+
+/// Log the comparison as a table: every flagged input, then the totals.
+pub fn log_table(comparison: &Comparison) {
+    let flagged: Vec<&InputComparison> = comparison
+        .inputs
+        .iter()
+        .filter(|i| i.verdict != Verdict::Unchanged)
+        .collect();
+
+    let width = flagged
+        .iter()
+        .map(|i| i.test_name.len())
+        .max()
+        .unwrap_or(0)
+        .max("TOTAL".len());
+
+    info!(
+        "{:<width$}  {:>10}  {:>10}  {:>10}  VERDICT",
+        "TEST", "STEPS", "COST", "TIME"
+    );
+    for input in &flagged {
+        info!(
+            "{:<width$}  {:>10}  {:>10}  {:>10}  {:?}",
+            input.test_name,
+            format_percent(input.steps.percent),
+            format_percent(input.cost.as_ref().and_then(|d| d.percent)),
+            format_percent(input.time.percent),
+            input.verdict
+        );
+    }
+    let totals = &comparison.totals;
+    info!(
+        "{:<width$}  {:>10}  {:>10}  {:>10}  ({} inputs)",
+        "TOTAL",
+        format_percent(totals.steps.percent),
+        format_percent(totals.cost.percent),
+        format_percent(totals.time.percent),
+        totals.inputs
+    );
+
+    info!("");
+    let unchanged = comparison
+        .inputs
+        .iter()
+        .filter(|i| i.verdict == Verdict::Unchanged)
+        .count();
+    info!(
+        "{} regressed, {} improved, {} unchanged (threshold {}%, budget {})",
+        comparison.regressed.len(),
+        comparison.improved.len(),
+        unchanged,
+        comparison.threshold,
+        comparison.budget
+    );
+    if !comparison.failed_in_candidate.is_empty() {
+        warn!(
+            "{} input(s) passed in the baseline but not in the candidate (counted as regressed): {:?}",
+            comparison.failed_in_candidate.len(),
+            comparison.failed_in_candidate
+        );
+    }
+    if !comparison.only_in_baseline.is_empty() {
+        warn!(
+            "{} input(s) only in the baseline: {:?}",
+            comparison.only_in_baseline.len(),
+            comparison.only_in_baseline
+        );
+    }
+    if !comparison.only_in_candidate.is_empty() {
+        warn!(
+            "{} input(s) only in the candidate: {:?}",
+            comparison.only_in_candidate.len(),
+            comparison.only_in_candidate
+        );
+    }
+}
+
+/// Check the runs' `metadata.json` say they can be compared, failing unless
+/// `--allow-incompatible`; returns the reasons they can't when allowed.
+fn check_compatible(
+    baseline: &Path,
+    candidate: &Path,
+    options: &CompareOptions,
+) -> Result<Vec<String>> {
+    let (Some(base), Some(cand)) = (RunMetadata::load(baseline)?, RunMetadata::load(candidate)?)
+    else {
+        warn!("A run has no metadata.json; can't check the runs are comparable");
+        return Ok(Vec::new());
+    };
+    let reasons = base.incompatibilities(&cand, options.time_threshold.is_some());
+    if reasons.is_empty() {
+        return Ok(reasons);
+    }
+    if !options.allow_incompatible {
+        anyhow::bail!(
+            "the runs aren't comparable ({}); pass --allow-incompatible to compare anyway",
+            reasons.join("; ")
+        );
+    }
+    for reason in &reasons {
+        warn!("Runs differ in {}", reason);
+    }
+    Ok(reasons)
+}
+
+fn format_percent(percent: Option<f64>) -> String {
+    percent
+        .map(|p| format!("{p:+.2}%"))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// The fields every result file has, passing or not.
+#[derive(Deserialize)]
+struct ResultStatus {
+    test_name: String,
+    #[serde(default)]
+    status: Status,
+}
+
+/// Load every result JSON in `folder`, keyed by test name. The other JSON
+/// files the runner writes there (`summary.json`, `metadata.json`,
+/// `<name>.publics.json`) are skipped; any other file that isn't a result is
+/// skipped with a warning.
+fn load_results(folder: &Path) -> Result<RunResults> {
+    let mut results = RunResults::default();
+    let entries =
+        fs::read_dir(folder).with_context(|| format!("Failed to read {}", folder.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.extension().is_none_or(|ext| ext != "json")
+            || name == "summary.json"
+            || name == metadata::FILE_NAME
+            || name.ends_with(".publics.json")
+        {
+            continue;
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if let Err(e) = load_result(&contents, &mut results) {
+            warn!("Skipping {}: not a benchmark result ({e})", path.display());
+        }
+    }
+    if results.passed.is_empty() && results.not_passed.is_empty() {
+        anyhow::bail!("No benchmark results found in {}", folder.display());
+    }
+    Ok(results)
+}
+
+fn load_result(contents: &str, results: &mut RunResults) -> serde_json::Result<()> {
+    let ResultStatus { test_name, status } = serde_json::from_str(contents)?;
+    if status == Status::Passed {
+        let result: BenchmarkResult = serde_json::from_str(contents)?;
+        results.passed.insert(test_name, result);
+    } else {
+        results.not_passed.insert(test_name, status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(budget: usize, time_threshold: Option<f64>) -> CompareOptions {
+        CompareOptions {
+            threshold: 1.0,
+            time_threshold,
+            budget,
+            allow_incompatible: false,
+        }
+    }
+
+    fn passed(results: &mut RunResults, name: &str, steps: u64, cost: Option<u64>, time: f64) {
+        let json = serde_json::json!({
+            "test_name": name,
+            "status": "passed",
+            "time": time,
+            "metrics": { "steps": steps, "cost": cost },
+        });
+        load_result(&json.to_string(), results).unwrap();
+    }
+
+    fn failed(results: &mut RunResults, name: &str, status: &str) {
+        let json = serde_json::json!({
+            "test_name": name,
+            "status": status,
+            "failure": { "kind": "other", "message": "boom" },
+        });
+        load_result(&json.to_string(), results).unwrap();
+    }
+
+    fn run(base: RunResults, cand: RunResults, options: &CompareOptions) -> Comparison {
+        compare_results(Path::new("base"), Path::new("cand"), base, cand, options)
+    }
+
+    #[test]
+    fn delta_percent_and_verdict() {
+        let delta = Delta::new(200.0, 210.0);
+        assert_eq!(delta.percent, Some(5.0));
+        assert_eq!(delta.verdict(5.0), Verdict::Unchanged);
+        assert_eq!(delta.verdict(4.9), Verdict::Regressed);
+        assert_eq!(Delta::new(200.0, 190.0).verdict(4.9), Verdict::Improved);
+        assert_eq!(Delta::new(200.0, 200.0).verdict(0.0), Verdict::Unchanged);
+    }
+
+    #[test]
+    fn delta_from_zero() {
+        let delta = Delta::new(0.0, 5.0);
+        assert_eq!(delta.percent, None);
+        assert_eq!(delta.verdict(1000.0), Verdict::Regressed);
+        assert_eq!(Delta::new(0.0, 0.0).verdict(0.0), Verdict::Unchanged);
+    }
+
+    #[test]
+    fn verdicts_and_failures_in_candidate() {
+        let mut base = RunResults::default();
+        passed(&mut base, "regressed", 100, Some(1000), 1.0);
+        passed(&mut base, "improved", 100, Some(1000), 1.0);
+        passed(&mut base, "unchanged", 100, Some(1000), 1.0);
+        passed(&mut base, "cost_regressed", 100, Some(1000), 1.0);
+        passed(&mut base, "missing", 100, None, 1.0);
+        passed(&mut base, "failing", 100, None, 1.0);
+        passed(&mut base, "over_limit", 100, None, 1.0);
+        failed(&mut base, "fixed", "failed");
+        failed(&mut base, "still_missing", "failed");
+
+        let mut cand = RunResults::default();
+        passed(&mut cand, "regressed", 105, Some(1000), 1.0);
+        passed(&mut cand, "improved", 90, Some(1000), 1.0);
+        passed(&mut cand, "unchanged", 100, Some(1005), 9.0);
+        passed(&mut cand, "cost_regressed", 100, Some(1100), 1.0);
+        failed(&mut cand, "failing", "failed");
+        failed(&mut cand, "over_limit", "limit_exceeded");
+        passed(&mut cand, "fixed", 100, None, 1.0);
+        failed(&mut cand, "new", "failed");
+
+        let comparison = run(base, cand, &options(5, None));
+        assert_eq!(
+            comparison.regressed,
+            [
+                "cost_regressed",
+                "failing",
+                "missing",
+                "over_limit",
+                "regressed"
+            ]
+        );
+        assert_eq!(comparison.improved, ["improved"]);
+        assert_eq!(
+            comparison.failed_in_candidate,
+            ["failing", "missing", "over_limit"]
+        );
+        assert_eq!(comparison.only_in_baseline, ["still_missing"]);
+        assert_eq!(comparison.only_in_candidate, ["new"]);
+        // Only inputs passing in both runs are compared and totalled.
+        assert_eq!(comparison.totals.inputs, 4);
+        assert_eq!(comparison.totals.steps.baseline, 400.0);
+        assert_eq!(comparison.totals.steps.candidate, 395.0);
+        assert_eq!(comparison.totals.cost.baseline, 4000.0);
+        assert_eq!(comparison.totals.cost.candidate, 4105.0);
+        assert!(comparison.passed());
+    }
+
+    #[test]
+    fn budget_counts_failures_in_candidate() {
+        let mut base = RunResults::default();
+        passed(&mut base, "a", 100, None, 1.0);
+        passed(&mut base, "b", 100, None, 1.0);
+        let mut cand = RunResults::default();
+        passed(&mut cand, "a", 100, None, 1.0);
+        failed(&mut cand, "b", "failed");
+
+        assert!(!run(base, cand, &options(0, None)).passed());
+    }
+
+    #[test]
+    fn time_is_flagged_only_with_a_time_threshold() {
+        let results = |time| {
+            let mut results = RunResults::default();
+            passed(&mut results, "a", 100, None, time);
+            results
+        };
+
+        let comparison = run(results(1.0), results(2.0), &options(0, None));
+        assert_eq!(comparison.inputs[0].verdict, Verdict::Unchanged);
+        assert_eq!(comparison.inputs[0].time.percent, Some(100.0));
+
+        let comparison = run(results(1.0), results(2.0), &options(0, Some(50.0)));
+        assert_eq!(comparison.regressed, ["a"]);
+        assert!(!comparison.passed());
+    }
+
+    #[test]
+    fn non_results_are_rejected() {
+        let mut results = RunResults::default();
+        assert!(load_result(r#"{"totals": {}}"#, &mut results).is_err());
+        // A passing status without the numbers is not a result either.
+        assert!(load_result(r#"{"test_name": "a"}"#, &mut results).is_err());
+        assert!(results.passed.is_empty() && results.not_passed.is_empty());
+    }
+}
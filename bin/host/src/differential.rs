@@ -0,0 +1,595 @@
+//! `differential`: execute one block under every client's guest program and
+//! compare what they commit. The clients validate the same block
+//! independently, so any disagreement in the committed block hash (or a client
+//! failing where others pass) is a consensus divergence in one of them.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::{error, info, warn};
+
+use input::{Client, ExecutionClient, InputFileName, RpcConfig, create_client};
+
+use crate::{
+    elfs::guest_program,
+    zisk::{ZiskClient, format_hash},
+};
+
+pub struct DifferentialOptions {
+    /// Clients to run; all compiled-in clients when empty.
+    pub clients: Vec<Client>,
+    /// Extra folders searched for the other clients' inputs.
+    pub inputs_dirs: Vec<PathBuf>,
+    /// Generate inputs that can't be found from this RPC endpoint.
+    pub rpc: Option<RpcConfig>,
+    pub emulator: bool,
+    pub unlock_mapped_memory: bool,
+}
+
+/// One client's execution of the block.
+#[derive(Debug, Serialize)]
+pub struct ClientRun {
+    pub client: &'static str,
+    pub input: Option<PathBuf>,
+    pub committed_block_hash: Option<String>,
+    pub steps: Option<u64>,
+    pub cost: Option<u64>,
+    pub time: Option<f64>,
+    /// Why the client produced no committed hash (no input, execution failed).
+    pub error: Option<String>,
+}
+
+impl ClientRun {
+    fn failed(client: &'static str, input: Option<PathBuf>, error: anyhow::Error) -> Self {
+        Self {
+            client,
+            input,
+            committed_block_hash: None,
+            steps: None,
+            cost: None,
+            time: None,
+            error: Some(format!("{error:#}")),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DifferentialReport {
+    pub chain: String,
+    pub block_number: u64,
+    /// Hash of the block header carried by the inputs, when a client's input
+    /// format has it.
+    pub input_block_hash: Option<String>,
+    pub runs: Vec<ClientRun>,
+    /// Human-readable reasons the clients disagree; empty when they agree.
+    pub divergences: Vec<String>,
+}
+
+impl DifferentialReport {
+    pub fn diverged(&self) -> bool {
+        !self.divergences.is_empty()
+    }
+
+    /// The block hash every client that ran committed, if any ran.
+    pub fn agreed_hash(&self) -> Option<&str> {
+        if self.diverged() {
+            return None;
+        }
+        self.runs
+            .iter()
+            .find_map(|r| r.committed_block_hash.as_deref())
+    }
+}
+
+/// Run `input`'s block through each client and compare the results.
+pub async fn run_differential(
+    input: &Path,
+    options: &DifferentialOptions,
+) -> Result<DifferentialReport> {
+    let (chain, block_prefix, source_client) = parse_input_name(input)?;
+    let source = create_client(source_client);
+    let source_bytes =
+        fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let block_number = source
+        .input_metadata(&source_bytes)
+        .with_context(|| format!("Failed to decode {}", input.display()))?
+        .block_number;
+    info!("Block {} on {}", block_number, chain);
+
+    let clients = if options.clients.is_empty() {
+        Client::value_variants().to_vec()
+    } else {
+        options.clients.clone()
+    };
+
+    let mut runs = Vec::with_capacity(clients.len());
+    let mut input_block_hash = None;
+    for client in clients {
+        let native = create_client(client);
+        let name = native.name();
+
+        let located = if client == source_client {
+            Ok(input.to_path_buf())
+        } else {
+            let from = Source {
+                path: input,
+                client: source.name(),
+                chain: &chain,
+                block_prefix: &block_prefix,
+                block_number,
+            };
+            locate_or_generate(native.as_ref(), &from, options).await
+        };
+        let client_input = match located {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("[{}] No input: {e:#}", name);
+                runs.push(ClientRun::failed(name, None, e));
+                continue;
+            }
+        };
+
+        if input_block_hash.is_none()
+            && let Ok(bytes) = fs::read(&client_input)
+        {
+            input_block_hash = native.input_block_hash(&bytes).ok();
+        }
+
+        info!("[{}] Executing {}", name, client_input.display());
+        let result = execute(
+            client,
+            native.as_ref(),
+            &client_input,
+            block_number,
+            options,
+        )
+        .await;
+        match result {
+            Ok(run) => runs.push(run),
+            Err(e) => {
+                error!("[{}] Execution failed: {e:#}", name);
+                runs.push(ClientRun::failed(name, Some(client_input), e));
+            }
+        }
+    }
+
+    let divergences = find_divergences(&runs, input_block_hash.as_ref());
+    Ok(DifferentialReport {
+        chain,
+        block_number,
+        input_block_hash: input_block_hash.as_ref().map(format_hash),
+        runs,
+        divergences,
+    })
+}
+
+async fn execute(
+    client: Client,
+    native: &dyn ExecutionClient,
+    input: &Path,
+    block_number: u64,
+    options: &DifferentialOptions,
+) -> Result<ClientRun> {
+    let bytes = fs::read(input)?;
+    let metadata = native.input_metadata(&bytes)?;
+    if metadata.block_number != block_number {
+        anyhow::bail!(
+            "{} carries block {}, not {}",
+            input.display(),
+            metadata.block_number,
+            block_number
+        );
+    }
+
+    let zisk_client = ZiskClient::for_execution(
+        guest_program(client),
+        options.emulator,
+        options.unlock_mapped_memory,
+        false,
+    )?;
+    zisk_client.setup().await?;
+    let output = zisk_client.execute(Some(input), None).await?;
+    let committed = native.committed_block_hash(&output.publics)?;
+
+    Ok(ClientRun {
+        client: native.name(),
+        input: Some(input.to_path_buf()),
+        committed_block_hash: Some(format_hash(&committed)),
+        steps: Some(output.metrics.steps),
+        cost: output.metrics.cost,
+        time: Some(output.metrics.duration.as_secs_f64()),
+        error: None,
+    })
+}
+
+fn find_divergences(runs: &[ClientRun], input_block_hash: Option<&[u8; 32]>) -> Vec<String> {
+    let mut divergences = Vec::new();
+    let committed: Vec<(&str, &str)> = runs
+        .iter()
+        .filter_map(|r| Some((r.client, r.committed_block_hash.as_deref()?)))
+        .collect();
+
+    let failed: Vec<&str> = runs
+        .iter()
+        .filter(|r| r.input.is_some() && r.committed_block_hash.is_none())
+        .map(|r| r.client)
+        .collect();
+    if !failed.is_empty() && !committed.is_empty() {
+        divergences.push(format!(
+            "{} failed while {} committed a block hash",
+            failed.join(", "),
+            committed
+                .iter()
+                .map(|(c, _)| *c)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    if let Some((first_client, first_hash)) = committed.first() {
+        for (client, hash) in &committed[1..] {
+            if hash != first_hash {
+                divergences.push(format!(
+                    "{client} committed {hash}, {first_client} committed {first_hash}"
+                ));
+            }
+        }
+    }
+
+    if let Some(expected) = input_block_hash.map(format_hash) {
+        for (client, hash) in &committed {
+            if *hash != expected {
+                divergences.push(format!(
+                    "{client} committed {hash}, the input's block hash is {expected}"
+                ));
+            }
+        }
+    }
+
+    divergences
+}
+
+/// Log the runs side by side, then the verdict.
+pub fn log_report(report: &DifferentialReport) {
+    info!("");
+    info!(
+        "{:<12}  {:>14}  {:>14}  {:>9}  COMMITTED BLOCK HASH",
+        "CLIENT", "STEPS", "COST", "TIME"
+    );
+    for run in &report.runs {
+        let num = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".into());
+        info!(
+            "{:<12}  {:>14}  {:>14}  {:>9}  {}",
+            run.client,
+            num(run.steps),
+            num(run.cost),
+            run.time
+                .map(|t| format!("{t:.2}s"))
+                .unwrap_or_else(|| "-".into()),
+            run.committed_block_hash
+                .as_deref()
+                .or(run.error.as_deref())
+                .unwrap_or("-")
+        );
+    }
+    info!("");
+
+    if report.diverged() {
+        for divergence in &report.divergences {
+            error!("CONSENSUS DIVERGENCE: {}", divergence);
+        }
+    } else if let Some(hash) = report.agreed_hash() {
+        info!(
+            "All clients that ran agree on block {}: {}",
+            report.block_number, hash
+        );
+    }
+}
+
+/// Split an input filename (`<chain>_<block>_<txs>_<mgas>_zec_<client>.bin`)
+/// into its chain, its `<chain>_<block>` prefix and the client it is for.
+fn parse_input_name(input: &Path) -> Result<(String, String, Client)> {
+    let file_name = input
+        .file_name()
+        .and_then(|s| s.to_str())
+        .with_context(|| format!("Invalid input path: {}", input.display()))?;
+    let name = InputFileName::parse(file_name).with_context(|| {
+        format!("{file_name} is not named `<chain>_<block>_<txs>_<mgas>_zec_<client>`")
+    })?;
+    let client = Client::value_variants()
+        .iter()
+        .copied()
+        .find(|c| create_client(*c).name() == name.client)
+        .with_context(|| {
+            format!(
+                "Unknown or disabled client `{}` in {file_name}",
+                name.client
+            )
+        })?;
+    let prefix = format!("{}_{}", name.chain, name.block_number);
+    Ok((name.chain, prefix, client))
+}
+
+/// The input `run_differential` was given, which other clients' inputs are
+/// looked up next to or converted from.
+struct Source<'a> {
+    path: &'a Path,
+    client: &'static str,
+    chain: &'a str,
+    block_prefix: &'a str,
+    block_number: u64,
+}
+
+/// Find `client`'s input for the block next to `source`, in the conventional
+/// `<client>-inputs` folders or in `--inputs-dir`. Failing that, convert
+/// `source` offline when there is a converter for the pair of clients, and
+/// as a last resort generate the input from RPC. Both write into
+/// `<client>-inputs/`.
+async fn locate_or_generate(
+    client: &dyn ExecutionClient,
+    source: &Source<'_>,
+    options: &DifferentialOptions,
+) -> Result<PathBuf> {
+    let name = client.name();
+    let block_prefix = source.block_prefix;
+    let default_dir = PathBuf::from(format!("{name}-inputs"));
+    let source_dir = source.path.parent().unwrap_or(Path::new("."));
+    let mut dirs = vec![source_dir.to_path_buf()];
+    if let Some(parent) = source_dir.parent() {
+        dirs.push(parent.join(&default_dir));
+    }
+    dirs.push(default_dir.clone());
+    dirs.extend(options.inputs_dirs.iter().cloned());
+
+    if let Some(found) = locate(&dirs, block_prefix, name) {
+        return Ok(found);
+    }
+
+    let rpc_url = options.rpc.as_ref().map(|rpc| rpc.url.as_str());
+    if let Some(converter) = Converter::for_clients(source.client, name) {
+        info!(
+            "[{}] Converting {} with {}",
+            name,
+            source.path.display(),
+            converter.exe
+        );
+        let converted = converter
+            .convert(source.path, source.chain, &default_dir, rpc_url)
+            .and_then(|()| {
+                locate(std::slice::from_ref(&default_dir), block_prefix, name)
+                    .with_context(|| format!("{} wrote no {block_prefix} input", converter.exe))
+            });
+        match converted {
+            Ok(path) => {
+                info!("[{}] Saved {}", name, path.display());
+                return Ok(path);
+            }
+            Err(e) => warn!("[{}] Conversion failed: {e:#}", name),
+        }
+    }
+
+    let Some(rpc) = &options.rpc else {
+        anyhow::bail!(
+            "no {name} input for {block_prefix} in {dirs:?}; pass --inputs-dir or --rpc-url"
+        );
+    };
+    info!("[{}] Generating the input from RPC", name);
+    let (stdin, stats) = client.from_rpc(rpc, source.block_number).await?;
+    fs::create_dir_all(&default_dir)?;
+    let path = default_dir.join(stats.output_filename(name));
+    stdin.save(&path)?;
+    info!("[{}] Saved {}", name, path.display());
+    Ok(path)
+}
+
+/// The first `<block_prefix>_*_zec_<client>.bin` in `dirs`.
+fn locate(dirs: &[PathBuf], block_prefix: &str, client: &str) -> Option<PathBuf> {
+    let prefix = format!("{block_prefix}_");
+    let suffix = format!("_zec_{client}.bin");
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .find(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name.starts_with(&prefix) && file_name.ends_with(&suffix)
+        })
+        .map(|entry| entry.path())
+}
+
+/// An offline input converter from `tools/`. The converters pin their own
+/// alloy versions, so they aren't linked in but run from `PATH`
+/// (`cargo install --path tools/<name>`).
+#[derive(Debug, PartialEq, Eq)]
+struct Converter {
+    exe: &'static str,
+    /// Whether it fetches the block's header fields from a (non-archive) RPC.
+    needs_rpc: bool,
+}
+
+impl Converter {
+    fn for_clients(from: &str, to: &str) -> Option<Self> {
+        match (from, to) {
+            ("reth", "ziskethone") => Some(Self {
+                exe: "reth-to-ziskethone",
+                needs_rpc: false,
+            }),
+            ("ziskethone", "reth") => Some(Self {
+                exe: "ziskethone-to-reth",
+                needs_rpc: true,
+            }),
+            _ => None,
+        }
+    }
+
+    fn convert(
+        &self,
+        source: &Path,
+        chain: &str,
+        output_dir: &Path,
+        rpc_url: Option<&str>,
+    ) -> Result<()> {
+        let mut command = Command::new(self.exe);
+        command
+            .arg(source)
+            .arg("--output-dir")
+            .arg(output_dir)
+            .arg("--chain")
+            .arg(chain);
+        if self.needs_rpc {
+            let url = rpc_url.with_context(|| format!("{} needs --rpc-url", self.exe))?;
+            command.arg("--rpc-url").arg(url);
+        }
+        let status = command
+            .status()
+            .with_context(|| format!("Failed to run {} (is it on PATH?)", self.exe))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {status}", self.exe);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    const HASH_A: [u8; 32] = [0xaa; 32];
+    const HASH_B: [u8; 32] = [0xbb; 32];
+
+    fn committed(client: &'static str, hash: &[u8; 32]) -> ClientRun {
+        ClientRun {
+            client,
+            input: Some(PathBuf::from(format!("{client}.bin"))),
+            committed_block_hash: Some(format_hash(hash)),
+            steps: Some(1),
+            cost: Some(1),
+            time: Some(1.0),
+            error: None,
+        }
+    }
+
+    fn failed(client: &'static str) -> ClientRun {
+        ClientRun::failed(
+            client,
+            Some(PathBuf::from(format!("{client}.bin"))),
+            anyhow!("boom"),
+        )
+    }
+
+    #[test]
+    fn agreement_is_no_divergence() {
+        let runs = [committed("reth", &HASH_A), committed("ethrex", &HASH_A)];
+        assert!(find_divergences(&runs, Some(&HASH_A)).is_empty());
+        assert!(find_divergences(&runs, None).is_empty());
+    }
+
+    #[test]
+    fn different_committed_hashes_diverge() {
+        let runs = [committed("reth", &HASH_A), committed("ethrex", &HASH_B)];
+        let divergences = find_divergences(&runs, None);
+        assert_eq!(divergences.len(), 1);
+        assert!(divergences[0].starts_with("ethrex committed 0xbb"));
+    }
+
+    #[test]
+    fn a_failure_while_others_commit_diverges() {
+        let runs = [committed("reth", &HASH_A), failed("ethrex")];
+        assert_eq!(
+            find_divergences(&runs, Some(&HASH_A)),
+            ["ethrex failed while reth committed a block hash"]
+        );
+    }
+
+    #[test]
+    fn a_client_without_an_input_does_not_diverge() {
+        let runs = [
+            committed("reth", &HASH_A),
+            ClientRun::failed("ethrex", None, anyhow!("no input")),
+        ];
+        assert!(find_divergences(&runs, Some(&HASH_A)).is_empty());
+    }
+
+    #[test]
+    fn all_failing_does_not_diverge() {
+        let runs = [failed("reth"), failed("ethrex")];
+        assert!(find_divergences(&runs, None).is_empty());
+    }
+
+    #[test]
+    fn disagreeing_with_the_input_header_diverges() {
+        let runs = [committed("reth", &HASH_A), committed("ethrex", &HASH_A)];
+        let divergences = find_divergences(&runs, Some(&HASH_B));
+        assert_eq!(divergences.len(), 2);
+        for (divergence, client) in divergences.iter().zip(["reth", "ethrex"]) {
+            assert!(divergence.starts_with(client));
+            assert!(divergence.ends_with(&format!(
+                "the input's block hash is {}",
+                format_hash(&HASH_B)
+            )));
+        }
+    }
+
+    #[test]
+    fn parses_input_names() {
+        let (chain, prefix, client) =
+            parse_input_name(Path::new("inputs/mainnet_25229957_107_9_zec_reth.bin")).unwrap();
+        assert_eq!(chain, "mainnet");
+        assert_eq!(prefix, "mainnet_25229957");
+        assert_eq!(client, Client::Reth);
+
+        let (.., client) = parse_input_name(Path::new("sepolia_1_0_0_zec_ethrex.bin")).unwrap();
+        assert_eq!(client, Client::Ethrex);
+    }
+
+    #[test]
+    fn rejects_malformed_input_names() {
+        for name in [
+            "mainnet_25229957.bin",
+            "mainnet_x_107_9_zec_reth.bin",
+            "mainnet_25229957_107_9_zec_geth.bin",
+        ] {
+            assert!(parse_input_name(Path::new(name)).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn converters_cover_the_offline_tools() {
+        assert_eq!(
+            Converter::for_clients("reth", "ziskethone").map(|c| c.needs_rpc),
+            Some(false)
+        );
+        assert_eq!(
+            Converter::for_clients("ziskethone", "reth").map(|c| c.needs_rpc),
+            Some(true)
+        );
+        assert_eq!(Converter::for_clients("reth", "ethrex"), None);
+    }
+
+    #[test]
+    fn locates_inputs_by_block_and_client() {
+        let dir = std::env::temp_dir().join(format!("differential-locate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "mainnet_100_1_1_zec_reth.bin",
+            "mainnet_1000_1_1_zec_ethrex.bin",
+            "mainnet_100_1_1_zec_ethrex.bin",
+        ] {
+            fs::write(dir.join(name), []).unwrap();
+        }
+        let dirs = [dir.clone()];
+        assert_eq!(
+            locate(&dirs, "mainnet_100", "ethrex"),
+            Some(dir.join("mainnet_100_1_1_zec_ethrex.bin"))
+        );
+        assert_eq!(locate(&dirs, "mainnet_1000", "reth"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use input::Client;
 use zisk_sdk::{GuestProgram, load_program};
 
 // ELF binaries for the host to load into the zkVM
@@ -5,3 +6,14 @@ pub const ELF_RETH: GuestProgram = load_program!("zec-reth");
 pub const ELF_ETHREX: GuestProgram = load_program!("zec-ethrex");
 pub use guest_ziskethone::ELF as ELF_ZISKETHONE;
 // Add more ELF binaries here as needed — see docs/adding-a-client.md
+
+/// The guest program ELF for a client.
+pub fn guest_program(client: Client) -> GuestProgram {
+    // To add a client, see docs/adding-a-client.md.
+    match client {
+        Client::Reth => ELF_RETH,
+        Client::Ethrex => ELF_ETHREX,
+        #[cfg(feature = "ziskethone")]
+        Client::ZiskEthOne => ELF_ZISKETHONE,
+    }
+}
@@ -0,0 +1,305 @@
+//! Classification of failed runs, so a result says at a glance whether a
+//! failure is in our inputs, the client, or the zkVM.
+//!
+//! Failures the host detects itself carry a typed error. Guest panics only
+//! reach the host as error text, so those are matched on the exact messages
+//! the guests panic with (`guest-reth`'s and `guest-ethrex`'s `run()`). Any
+//! other failure inside the ZisK SDK is an [`ExecutionFailed`], classified by
+//! the action that failed and whether hints drove it: the SDK's errors carry
+//! no type that tells steps running out from hints not matching.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    benchmark::BlockHashMismatch,
+    cli::Action,
+    hints_gen::NondeterministicHints,
+    limits::{Limit, LimitExceeded},
+    zisk::ExecutionFailed,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The input (or hints) could not be read or deserialized.
+    InputDecode,
+    /// Transaction signer recovery failed before validation.
+    SignatureRecovery,
+    /// The client rejected the block; `variant` is the validation error's
+    /// variant name (e.g. `StatelessValidationError::InvalidBlockHeader`).
+    StatelessValidation {
+        variant: Option<String>,
+    },
+    /// ZisK failed the run without the guest panicking: out of steps, a
+    /// hints mismatch when `with_hints`, or a zkVM bug.
+    Zkvm {
+        action: Action,
+        with_hints: bool,
+    },
+    /// Generating the same input's hints twice gave different hints
+    /// (`--check-determinism`).
+    NondeterministicHints,
+    /// The guest committed a different block hash than expected.
+    BlockHashMismatch,
+    /// The run went over `--steps-threshold` or `--timeout`. Reported apart from
+    /// the failures: the input may well be valid.
+    LimitExceeded {
+        limit: Limit,
+    },
+    Other,
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InputDecode => write!(f, "input decode"),
+            Self::SignatureRecovery => write!(f, "signature recovery"),
+            Self::StatelessValidation { variant: Some(v) } => {
+                write!(f, "stateless validation ({v})")
+            }
+            Self::StatelessValidation { variant: None } => write!(f, "stateless validation"),
+            Self::Zkvm { action, with_hints } => {
+                write!(f, "zkvm ({action:?}")?;
+                if *with_hints {
+                    write!(f, " with hints")?;
+                }
+                write!(f, ")")
+            }
+            Self::NondeterministicHints => write!(f, "nondeterministic hints"),
+            Self::BlockHashMismatch => write!(f, "block hash mismatch"),
+            Self::LimitExceeded { limit } => write!(f, "limit exceeded ({limit})"),
+            Self::Other => write!(f, "other"),
+        }
+    }
+}
+
+impl FailureKind {
+    /// Category name without the variant, for grouping.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::InputDecode => "input_decode",
+            Self::SignatureRecovery => "signature_recovery",
+            Self::StatelessValidation { .. } => "stateless_validation",
+            Self::Zkvm { .. } => "zkvm",
+            Self::NondeterministicHints => "nondeterministic_hints",
+            Self::BlockHashMismatch => "block_hash_mismatch",
+            Self::LimitExceeded { .. } => "limit_exceeded",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// A classified failure: its category plus the full error chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    #[serde(flatten)]
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        let message = format!("{error:#}");
+        let kind = if let Some(exceeded) = error.downcast_ref::<LimitExceeded>() {
+            FailureKind::LimitExceeded {
+                limit: exceeded.limit,
+            }
+        } else if error.downcast_ref::<NondeterministicHints>().is_some() {
+            FailureKind::NondeterministicHints
+        } else if error.downcast_ref::<BlockHashMismatch>().is_some() {
+            FailureKind::BlockHashMismatch
+        } else {
+            classify(&message, error.downcast_ref::<ExecutionFailed>())
+        };
+        Self { kind, message }
+    }
+}
+
+/// Panic message of `validate_block_stateless`/`validate_block` in the guests;
+/// the validation error's `Debug` form follows it.
+const VALIDATION_FAILED: &str = "Block validation failed";
+
+/// Panic message of `verify_signatures` in `guest-reth`.
+const SIGNATURE_FAILED: &str = "Signature verification failed";
+
+/// Messages of an input that could not be read: `guest-ethrex`'s panic on its
+/// input, and the host's own when loading the input or hints file.
+const DECODE_FAILED: [&str; 3] = [
+    "Failed to deserialize",
+    "Failed to load input file",
+    "Failed to load hints file",
+];
+
+fn classify(message: &str, execution: Option<&ExecutionFailed>) -> FailureKind {
+    if let Some(pos) = message.find(VALIDATION_FAILED) {
+        return FailureKind::StatelessValidation {
+            variant: variant_name(&message[pos + VALIDATION_FAILED.len()..]),
+        };
+    }
+    if message.contains(SIGNATURE_FAILED) {
+        return FailureKind::SignatureRecovery;
+    }
+    if DECODE_FAILED.iter().any(|p| message.contains(p)) {
+        return FailureKind::InputDecode;
+    }
+    match execution {
+        Some(execution) => FailureKind::Zkvm {
+            action: execution.action,
+            with_hints: execution.with_hints,
+        },
+        None => FailureKind::Other,
+    }
+}
+
+/// The leading identifier of a `Debug`-formatted error, e.g.
+/// `": InvalidBlockHeader(..)"` -> `InvalidBlockHeader`.
+fn variant_name(rest: &str) -> Option<String> {
+    let rest = rest.trim_start_matches([':', ' ']);
+    let name: String = rest
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        .then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    fn sdk_failed(action: Action, with_hints: bool, sdk: &str) -> anyhow::Error {
+        anyhow::Error::new(ExecutionFailed {
+            action,
+            with_hints,
+            source: anyhow!("{sdk}"),
+        })
+        .context("Failed to execute mainnet_24628607_reth.bin")
+    }
+
+    fn execution_failed(sdk: &str) -> anyhow::Error {
+        sdk_failed(Action::Execute, false, sdk)
+    }
+
+    #[test]
+    fn every_kind_is_classified() {
+        let cases: Vec<(anyhow::Error, FailureKind)> = vec![
+            (
+                anyhow!("Failed to deserialize EthrexInput: unexpected end of input"),
+                FailureKind::InputDecode,
+            ),
+            (
+                anyhow!("No such file or directory").context("Failed to load input file"),
+                FailureKind::InputDecode,
+            ),
+            (
+                anyhow!("No such file or directory").context("Failed to load hints file"),
+                FailureKind::InputDecode,
+            ),
+            (
+                execution_failed("guest panicked: Signature verification failed: Err"),
+                FailureKind::SignatureRecovery,
+            ),
+            (
+                execution_failed(
+                    "guest panicked: Block validation failed: InvalidBlockHeader(GasUsed)",
+                ),
+                FailureKind::StatelessValidation {
+                    variant: Some("InvalidBlockHeader".to_string()),
+                },
+            ),
+            (
+                execution_failed("Block validation failed: <unknown>"),
+                FailureKind::StatelessValidation { variant: None },
+            ),
+            (
+                sdk_failed(Action::Execute, false, "emulator stopped"),
+                FailureKind::Zkvm {
+                    action: Action::Execute,
+                    with_hints: false,
+                },
+            ),
+            (
+                sdk_failed(Action::VerifyConstraints, true, "emulator stopped"),
+                FailureKind::Zkvm {
+                    action: Action::VerifyConstraints,
+                    with_hints: true,
+                },
+            ),
+            (
+                sdk_failed(Action::Prove, false, "emulator stopped"),
+                FailureKind::Zkvm {
+                    action: Action::Prove,
+                    with_hints: false,
+                },
+            ),
+            (
+                anyhow::Error::new(NondeterministicHints {
+                    offset: 64,
+                    divergence: "hint #3 differs".to_string(),
+                    rerun_path: PathBuf::from("a.hints.rerun"),
+                }),
+                FailureKind::NondeterministicHints,
+            ),
+            (
+                anyhow::Error::new(BlockHashMismatch {
+                    committed: [1; 32],
+                    expected: [2; 32],
+                }),
+                FailureKind::BlockHashMismatch,
+            ),
+            (
+                anyhow::Error::new(LimitExceeded::timeout(Duration::from_secs(30))),
+                FailureKind::LimitExceeded {
+                    limit: Limit::Timeout(30),
+                },
+            ),
+            (anyhow!("connection reset by peer"), FailureKind::Other),
+        ];
+
+        for (error, expected) in cases {
+            assert_eq!(Failure::from_error(&error).kind, expected, "{error:#}");
+        }
+    }
+
+    #[test]
+    fn guest_panics_win_over_the_action_that_surfaced_them() {
+        for action in [Action::Execute, Action::VerifyConstraints, Action::Prove] {
+            let error = sdk_failed(action, true, "Signature verification failed: Err");
+            assert_eq!(
+                Failure::from_error(&error).kind,
+                FailureKind::SignatureRecovery
+            );
+        }
+    }
+
+    #[test]
+    fn host_errors_are_not_blamed_on_the_zkvm() {
+        let cases = [
+            anyhow!("Permission denied").context("Failed to create hints output dir"),
+            anyhow!("Connection refused").context("Failed to connect to the hints socket"),
+            anyhow!("Permission denied")
+                .context("Failed to regenerate hints for the determinism check"),
+            anyhow!("execution exceeded the max steps"),
+        ];
+        for error in cases {
+            assert_eq!(
+                Failure::from_error(&error).kind,
+                FailureKind::Other,
+                "{error:#}"
+            );
+        }
+    }
+
+    #[test]
+    fn zkvm_failures_serialize_with_their_action() {
+        let failure = Failure::from_error(&sdk_failed(Action::Prove, true, "boom"));
+        let json = serde_json::to_value(&failure).unwrap();
+        assert_eq!(json["kind"], "zkvm");
+        assert_eq!(json["action"], "Prove");
+        assert_eq!(json["with_hints"], true);
+    }
+}
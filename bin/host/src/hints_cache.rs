@@ -0,0 +1,183 @@
+//! `--hints-cache`: hints stored by what they are generated from — the input's
+//! bytes, the client, and the guest ELF that consumes them — rather than by the
+//! input's file name. An input's hints are generated once and reused until one
+//! of those changes; a renamed input still hits, and a changed guest misses
+//! instead of reusing stale hints.
+//!
+//! Layout: `<dir>/<client>/<elf hash>/<input hash>.hints`, both hashes
+//! keccak-256 in hex. Shared by `hints-gen` and `host --gen-hints`.
+
+use anyhow::{Context, Result};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tiny_keccak::{Hasher, Keccak};
+use tracing::info;
+use zisk_sdk::ZiskStdin;
+
+use input::{Client, ExecutionClient, generate_hints_to_file};
+
+use crate::{elfs::guest_program, hints_gen};
+
+pub struct HintsCache {
+    root: PathBuf,
+    /// `<root>/<client>/<elf hash>`: where this client's and ELF's hints live.
+    dir: PathBuf,
+}
+
+/// A cache lookup.
+pub struct CachedHints {
+    pub path: PathBuf,
+    /// Execution and total time of the generation; `None` on a cache hit.
+    pub generated: Option<(Duration, Duration)>,
+}
+
+impl HintsCache {
+    pub fn new(root: &Path, client: Client) -> Result<Self> {
+        let client_name = input::create_client(client).name();
+        Self::for_elf(root, client_name, guest_program(client).elf())
+    }
+
+    fn for_elf(root: &Path, client_name: &str, elf: &[u8]) -> Result<Self> {
+        let dir = root.join(client_name).join(keccak_hex(elf));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create hints cache dir: {}", dir.display()))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            dir,
+        })
+    }
+
+    /// The `--hints-cache` directory, shared by every client and ELF.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the hints of an input with these bytes live.
+    fn path(&self, input_bytes: &[u8]) -> PathBuf {
+        self.dir
+            .join(keccak_hex(input_bytes))
+            .with_extension("hints")
+    }
+
+    /// `input`'s hints, generated into the cache on a miss. With `check`, the
+    /// hints are also regenerated and compared, hit or miss. A timed-out
+    /// generation leaves nothing in the cache.
+    pub fn get_or_generate(
+        &self,
+        input: &Path,
+        stdin: &ZiskStdin,
+        client: &Arc<dyn ExecutionClient>,
+        check: bool,
+        timeout: Option<Duration>,
+    ) -> Result<CachedHints> {
+        let bytes =
+            std::fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+        let path = self.path(&bytes);
+
+        let generated = if path.is_file() {
+            info!("Cached hints: {} -> {}", input.display(), path.display());
+            None
+        } else {
+            info!(
+                "Generating hints: {} -> {}",
+                input.display(),
+                path.display()
+            );
+            // Written aside and renamed into place, so an interrupted run or a
+            // concurrent worker never leaves a partial file behind a hit.
+            let partial = path.with_extension(format!("hints.{}.partial", std::process::id()));
+            let timings = generate_hints_to_file(stdin, partial.clone(), timeout, client)
+                .inspect_err(|_| {
+                    let _ = std::fs::remove_file(&partial);
+                })?;
+            std::fs::rename(&partial, &path)
+                .with_context(|| format!("Failed to move hints into {}", path.display()))?;
+            Some(timings)
+        };
+
+        if check {
+            hints_gen::check_determinism(stdin, &path, timeout, client)?;
+        }
+        Ok(CachedHints { path, generated })
+    }
+}
+
+pub(crate) fn keccak_hex(bytes: &[u8]) -> String {
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(bytes);
+    keccak.finalize(&mut hash);
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hints-cache-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn keys_cover_input_bytes_client_and_elf() {
+        let root = root("keys");
+        let cache = HintsCache::for_elf(&root, "reth", b"elf v1").unwrap();
+        assert_eq!(cache.dir(), root.join("reth").join(keccak_hex(b"elf v1")));
+        assert!(cache.dir().is_dir());
+
+        // Same bytes, same hints, whatever the input is called.
+        assert_eq!(cache.path(b"block"), cache.path(b"block"));
+        assert_ne!(cache.path(b"block"), cache.path(b"other block"));
+        assert_eq!(
+            cache.path(b"block"),
+            cache.dir().join(format!("{}.hints", keccak_hex(b"block")))
+        );
+
+        let other_client = HintsCache::for_elf(&root, "ethrex", b"elf v1").unwrap();
+        let other_elf = HintsCache::for_elf(&root, "reth", b"elf v2").unwrap();
+        for other in [&other_client, &other_elf] {
+            assert_ne!(other.path(b"block"), cache.path(b"block"));
+        }
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn a_hit_is_not_regenerated_and_a_miss_leaves_nothing_behind() {
+        let root = root("lookup");
+        let cache = HintsCache::new(&root, Client::Reth).unwrap();
+        let client: Arc<dyn ExecutionClient> = input::create_client(Client::Reth).into();
+        let stdin = ZiskStdin::new();
+
+        let renamed = root.join("renamed.bin");
+        std::fs::write(&renamed, b"cached input").unwrap();
+        std::fs::write(cache.path(b"cached input"), b"hints").unwrap();
+        let hit = cache
+            .get_or_generate(&renamed, &stdin, &client, false, None)
+            .unwrap();
+        assert_eq!(hit.path, cache.path(b"cached input"));
+        assert!(hit.generated.is_none());
+
+        // Nothing to generate hints from: the miss fails, and neither the
+        // hints nor a partial file are left in the cache.
+        let missing = root.join("missing.bin");
+        std::fs::write(&missing, b"uncached input").unwrap();
+        assert!(
+            cache
+                .get_or_generate(&missing, &stdin, &client, false, None)
+                .is_err()
+        );
+        let left: Vec<_> = std::fs::read_dir(cache.dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(left, [cache.path(b"cached input")]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
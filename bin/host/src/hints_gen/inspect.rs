@@ -0,0 +1,450 @@
+//! `hints-gen inspect` and `hints-gen diff`: read `.hints` files (the framing
+//! `scripts/parse_hints.sh` decodes), check that framing, and find the first
+//! hint where two runs' hints diverge.
+//!
+//! A hints file is a `u64` start header (0), then the hints, each a
+//! little-endian `u64` header — hint id in the high 32 bits, data length in
+//! bytes in the low 32 — followed by the data, zero-padded to 8 bytes.
+
+use anyhow::{Context, Result};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+use tracing::{info, warn};
+
+const WORD: u64 = 8;
+
+/// One framed hint. `index` is 1-based, as in `parse_hints.sh`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HintHeader {
+    pub index: usize,
+    /// Offset of the hint's header in the file.
+    pub offset: u64,
+    pub id: u32,
+    pub data_len: u32,
+}
+
+impl HintHeader {
+    fn pad(&self) -> u64 {
+        (WORD - (self.data_len as u64 % WORD)) % WORD
+    }
+
+    /// Header, data and padding.
+    fn total_len(&self) -> u64 {
+        WORD + self.data_len as u64 + self.pad()
+    }
+}
+
+impl fmt::Display for HintHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} at offset {}: hint_id=0x{:08x}, data_len={}",
+            self.index, self.offset, self.id, self.data_len
+        )
+    }
+}
+
+/// Streams the hints of a file, checking their framing as it goes.
+struct HintReader {
+    reader: BufReader<File>,
+    file_len: u64,
+    offset: u64,
+    next_index: usize,
+    start_header: u64,
+}
+
+impl HintReader {
+    fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let file_len = file.metadata()?.len();
+        if file_len < WORD {
+            anyhow::bail!(
+                "{} is too small for a hints file (< 8 bytes)",
+                path.display()
+            );
+        }
+        let mut reader = BufReader::new(file);
+        let start_header = read_u64(&mut reader)?;
+        Ok(Self {
+            reader,
+            file_len,
+            offset: WORD,
+            next_index: 1,
+            start_header,
+        })
+    }
+
+    /// The next hint and its data, `None` at the end of the file.
+    fn next(&mut self) -> Result<Option<(HintHeader, Vec<u8>)>> {
+        let remaining = self.file_len - self.offset;
+        if remaining == 0 {
+            return Ok(None);
+        }
+        if remaining < WORD {
+            anyhow::bail!(
+                "truncated hint header at offset {} ({} trailing bytes)",
+                self.offset,
+                remaining
+            );
+        }
+
+        let raw = read_u64(&mut self.reader)?;
+        let header = HintHeader {
+            index: self.next_index,
+            offset: self.offset,
+            id: (raw >> 32) as u32,
+            data_len: raw as u32,
+        };
+        if header.total_len() > remaining {
+            anyhow::bail!(
+                "hint {header} runs past the end of the file ({} bytes framed, {} left)",
+                header.total_len(),
+                remaining
+            );
+        }
+
+        let mut data = vec![0; header.data_len as usize];
+        self.reader.read_exact(&mut data)?;
+        let mut pad = [0u8; WORD as usize];
+        let pad = &mut pad[..header.pad() as usize];
+        self.reader.read_exact(pad)?;
+        if pad.iter().any(|b| *b != 0) {
+            anyhow::bail!("hint {header} has non-zero padding: {pad:02x?}");
+        }
+
+        self.offset += header.total_len();
+        self.next_index += 1;
+        Ok(Some((header, data)))
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buf = [0u8; WORD as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Count and size of one hint type.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HintTypeStats {
+    pub count: usize,
+    pub data_bytes: u64,
+    /// Headers, data and padding.
+    pub total_bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct Inspection {
+    pub start_header: u64,
+    pub hints: usize,
+    pub file_bytes: u64,
+    pub by_type: BTreeMap<u32, HintTypeStats>,
+    /// Set when the framing is broken; the stats cover the hints before it.
+    pub error: Option<String>,
+}
+
+/// Walk `path`'s hints up to and including hint `stop` (1-based), calling
+/// `each` on every header.
+pub fn inspect(
+    path: &Path,
+    stop: Option<usize>,
+    mut each: impl FnMut(&HintHeader),
+) -> Result<Inspection> {
+    let mut reader = HintReader::open(path)?;
+    let mut inspection = Inspection {
+        start_header: reader.start_header,
+        hints: 0,
+        file_bytes: reader.file_len,
+        by_type: BTreeMap::new(),
+        error: None,
+    };
+
+    while stop.is_none_or(|stop| reader.next_index <= stop) {
+        match reader.next() {
+            Ok(Some((header, _))) => {
+                each(&header);
+                inspection.hints += 1;
+                let stats = inspection.by_type.entry(header.id).or_default();
+                stats.count += 1;
+                stats.data_bytes += header.data_len as u64;
+                stats.total_bytes += header.total_len();
+            }
+            Ok(None) => break,
+            Err(e) => {
+                inspection.error = Some(format!("{e:#}"));
+                break;
+            }
+        }
+    }
+    Ok(inspection)
+}
+
+/// Log the histogram of hint types, largest first.
+pub fn log_inspection(path: &Path, inspection: &Inspection) {
+    info!(
+        "{}: {} hints, {} bytes",
+        path.display(),
+        inspection.hints,
+        inspection.file_bytes
+    );
+    if inspection.start_header != 0 {
+        warn!(
+            "Start header is 0x{:016x}, expected 0",
+            inspection.start_header
+        );
+    }
+
+    let mut types: Vec<(&u32, &HintTypeStats)> = inspection.by_type.iter().collect();
+    types.sort_by(|a, b| b.1.total_bytes.cmp(&a.1.total_bytes));
+    info!(
+        "{:<12}  {:>10}  {:>14}  {:>14}  {:>6}",
+        "HINT_ID", "COUNT", "DATA BYTES", "TOTAL BYTES", "SHARE"
+    );
+    let framed = inspection.file_bytes.max(1) as f64;
+    for (id, stats) in types {
+        info!(
+            "0x{:08x}    {:>10}  {:>14}  {:>14}  {:>5.1}%",
+            id,
+            stats.count,
+            stats.data_bytes,
+            stats.total_bytes,
+            stats.total_bytes as f64 / framed * 100.0
+        );
+    }
+}
+
+/// Where two hints files first differ.
+#[derive(Debug)]
+pub struct Divergence {
+    /// The differing hint in each file; `None` where that file has ended.
+    pub a: Option<HintHeader>,
+    pub b: Option<HintHeader>,
+    /// First differing byte within the data, when both hints have the same
+    /// id and length.
+    pub data_offset: Option<usize>,
+}
+
+impl Divergence {
+    /// Byte offset of the first difference. The files are identical before
+    /// the differing hint, so it starts at the same offset in both.
+    pub fn offset(&self) -> u64 {
+        let header = self.a.or(self.b).expect("a divergence has a hint");
+        match self.data_offset {
+            Some(data_offset) => header.offset + WORD + data_offset as u64,
+            None => header.offset,
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |h: &Option<HintHeader>| match h {
+            Some(h) => h.to_string(),
+            None => "end of file".to_string(),
+        };
+        write!(f, "a: {}; b: {}", side(&self.a), side(&self.b))?;
+        if let Some(offset) = self.data_offset {
+            write!(f, "; data differs from byte {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+pub enum DiffOutcome {
+    Identical { hints: usize },
+    Diverged(Divergence),
+}
+
+/// Compare two hints files hint by hint.
+pub fn diff(a: &Path, b: &Path) -> Result<DiffOutcome> {
+    let mut reader_a = HintReader::open(a)?;
+    let mut reader_b = HintReader::open(b)?;
+    if reader_a.start_header != reader_b.start_header {
+        warn!(
+            "Start headers differ: 0x{:016x} vs 0x{:016x}",
+            reader_a.start_header, reader_b.start_header
+        );
+    }
+
+    let mut hints = 0;
+    loop {
+        let next_a = reader_a
+            .next()
+            .with_context(|| format!("Invalid hints file {}", a.display()))?;
+        let next_b = reader_b
+            .next()
+            .with_context(|| format!("Invalid hints file {}", b.display()))?;
+        let (header_a, data_a, header_b, data_b) = match (next_a, next_b) {
+            (None, None) => return Ok(DiffOutcome::Identical { hints }),
+            (Some((h, d)), Some((hb, db))) => (h, d, hb, db),
+            (a, b) => {
+                return Ok(DiffOutcome::Diverged(Divergence {
+                    a: a.map(|(h, _)| h),
+                    b: b.map(|(h, _)| h),
+                    data_offset: None,
+                }));
+            }
+        };
+
+        if header_a.id != header_b.id || header_a.data_len != header_b.data_len {
+            return Ok(DiffOutcome::Diverged(Divergence {
+                a: Some(header_a),
+                b: Some(header_b),
+                data_offset: None,
+            }));
+        }
+        if let Some(offset) = data_a.iter().zip(&data_b).position(|(x, y)| x != y) {
+            return Ok(DiffOutcome::Diverged(Divergence {
+                a: Some(header_a),
+                b: Some(header_b),
+                data_offset: Some(offset),
+            }));
+        }
+        hints += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// A hints file: the start header, then each `(id, data)` framed.
+    fn hints(hints: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut buf = 0u64.to_le_bytes().to_vec();
+        for (id, data) in hints {
+            let header = ((*id as u64) << 32) | data.len() as u64;
+            buf.extend_from_slice(&header.to_le_bytes());
+            buf.extend_from_slice(data);
+            buf.resize(buf.len().next_multiple_of(WORD as usize), 0);
+        }
+        buf
+    }
+
+    /// Write `bytes` to a file of this test's own.
+    fn file(name: &str, bytes: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("inspect-{}-{name}.hints", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    const HINTS: [(u32, &[u8]); 3] = [(1, b"abc"), (2, b"12345678"), (1, b"")];
+
+    #[test]
+    fn inspects_a_valid_stream() {
+        let bytes = hints(&HINTS);
+        let path = file("valid", &bytes);
+        let mut headers = Vec::new();
+        let inspection = inspect(&path, None, |h| headers.push(*h)).unwrap();
+
+        assert_eq!(inspection.error, None);
+        assert_eq!(inspection.hints, 3);
+        assert_eq!(inspection.file_bytes, bytes.len() as u64);
+        assert_eq!(inspection.by_type[&1].count, 2);
+        assert_eq!(inspection.by_type[&1].data_bytes, 3);
+        assert_eq!(inspection.by_type[&1].total_bytes, 16 + 8);
+        assert_eq!(inspection.by_type[&2].total_bytes, 16);
+        let offsets: Vec<u64> = headers.iter().map(|h| h.offset).collect();
+        assert_eq!(offsets, [8, 24, 40]);
+        assert_eq!(headers[2].index, 3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn stops_after_the_given_hint() {
+        let path = file("stop", &hints(&HINTS));
+        let inspection = inspect(&path, Some(2), |_| {}).unwrap();
+        assert_eq!(inspection.hints, 2);
+        assert_eq!(inspection.error, None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reports_a_truncated_header() {
+        let mut bytes = hints(&HINTS[..1]);
+        bytes.extend_from_slice(&[0; 4]);
+        let path = file("truncated-header", &bytes);
+        let inspection = inspect(&path, None, |_| {}).unwrap();
+        assert_eq!(inspection.hints, 1);
+        let error = inspection.error.unwrap();
+        assert!(
+            error.contains("truncated hint header at offset 24"),
+            "{error}"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reports_a_truncated_payload() {
+        let bytes = hints(&HINTS[..2]);
+        let path = file("truncated-payload", &bytes[..bytes.len() - 1]);
+        let inspection = inspect(&path, None, |_| {}).unwrap();
+        assert_eq!(inspection.hints, 1);
+        let error = inspection.error.unwrap();
+        assert!(error.contains("runs past the end of the file"), "{error}");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_file_shorter_than_the_start_header() {
+        let path = file("short", &[0; 4]);
+        assert!(inspect(&path, None, |_| {}).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    fn diverged(a: &[(u32, &[u8])], b: &[(u32, &[u8])], name: &str) -> Option<Divergence> {
+        let (a_bytes, b_bytes) = (hints(a), hints(b));
+        let (path_a, path_b) = (
+            file(&format!("{name}-a"), &a_bytes),
+            file(&format!("{name}-b"), &b_bytes),
+        );
+        let outcome = diff(&path_a, &path_b).unwrap();
+        std::fs::remove_file(path_a).unwrap();
+        std::fs::remove_file(path_b).unwrap();
+        match outcome {
+            DiffOutcome::Identical { hints } => {
+                assert_eq!(hints, a.len());
+                None
+            }
+            DiffOutcome::Diverged(divergence) => Some(divergence),
+        }
+    }
+
+    #[test]
+    fn identical_files_do_not_diverge() {
+        assert!(diverged(&HINTS, &HINTS, "identical").is_none());
+    }
+
+    #[test]
+    fn diverging_data_points_at_the_first_differing_byte() {
+        let divergence = diverged(&HINTS, &[HINTS[0], (2, b"12345x78"), HINTS[2]], "data").unwrap();
+        assert_eq!(divergence.data_offset, Some(5));
+        // The second hint's header is at 24, its data at 32.
+        assert_eq!(divergence.offset(), 32 + 5);
+    }
+
+    #[test]
+    fn diverging_headers_point_at_the_hint() {
+        let divergence = diverged(&HINTS, &[HINTS[0], (3, b"12345678")], "header").unwrap();
+        assert_eq!(divergence.data_offset, None);
+        assert_eq!(divergence.offset(), 24);
+        assert_eq!(divergence.a.unwrap().id, 2);
+        assert_eq!(divergence.b.unwrap().id, 3);
+    }
+
+    #[test]
+    fn a_shorter_file_diverges_where_it_ends() {
+        let divergence = diverged(&HINTS, &HINTS[..2], "shorter").unwrap();
+        assert!(divergence.b.is_none());
+        assert_eq!(divergence.offset(), 40);
+        assert!(divergence.to_string().contains("b: end of file"));
+    }
+}
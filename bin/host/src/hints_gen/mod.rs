@@ -1,11 +1,21 @@
+pub mod inspect;
+pub mod serve;
+mod workers;
+
 use anyhow::{Context, Result};
-use clap::Args;
+use clap::{Args, Subcommand};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 use zisk_sdk::ZiskStdin;
 
-use input::{Client, ExecutionClient, create_client, generate_hints_to_file};
+use input::{Client, ExecutionClient, HintsTimeout, create_client, generate_hints_to_file};
+
+use crate::{
+    hints_cache::HintsCache,
+    inputs::{self, InputFilter},
+};
 
 #[derive(Args, Debug, Clone)]
 pub struct HintsGenArgs {
@@ -13,97 +23,329 @@ pub struct HintsGenArgs {
     #[arg(conflicts_with = "inputs_folder")]
     inputs: Vec<PathBuf>,
 
-    /// Directory containing .bin input files (processed in sorted order)
+    /// Directory searched recursively for .bin input files (processed in
+    /// sorted order)
     #[arg(short = 'f', long)]
     inputs_folder: Option<PathBuf>,
 
+    /// Which inputs to generate hints for
+    #[command(flatten)]
+    filter: InputFilter,
+
     /// Output directory for hints files (default: <client>-hints)
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "hints_cache")]
     output_dir: Option<PathBuf>,
 
+    /// Store hints in this content-addressed cache instead, keyed by input
+    /// bytes, client and guest ELF; inputs with cached hints are skipped
+    #[arg(long)]
+    hints_cache: Option<PathBuf>,
+
     /// Execution client
     #[arg(short, long, value_enum, default_value = "reth")]
     client: Client,
+
+    /// Number of inputs to generate hints for concurrently, each in its own
+    /// worker process
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Generate each input's hints twice and fail if the two runs differ
+    #[arg(long)]
+    check_determinism: bool,
+
+    /// Give up on an input whose hints generation runs longer than this many
+    /// seconds, and move on to the next. Each input then runs in a worker
+    /// process of its own, even without --jobs
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+}
+
+/// Exit code of a run whose only failures are timeouts (as `timeout(1)`'s),
+/// so `--jobs` can tell a worker that timed out from one that failed.
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Tools over existing hints files
+#[derive(Subcommand, Debug, Clone)]
+pub enum HintsCommand {
+    /// Check a hints file's framing and print a histogram of its hint types
+    Inspect {
+        /// Hints file
+        file: PathBuf,
+
+        /// Stop after this hint (1-based, as `scripts/parse_hints.sh`)
+        #[arg(long)]
+        stop: Option<usize>,
+
+        /// Also print every hint's header
+        #[arg(short, long)]
+        list: bool,
+    },
+    /// Report the first hint at which two hints files differ
+    Diff {
+        /// Hints file
+        a: PathBuf,
+
+        /// Hints file to compare against `a`
+        b: PathBuf,
+    },
+    /// Stay up and generate hints for blocks sent over a Unix socket or TCP
+    Serve(serve::ServeArgs),
+}
+
+pub fn run_command(command: HintsCommand) -> Result<()> {
+    match command {
+        HintsCommand::Inspect { file, stop, list } => {
+            let inspection = inspect::inspect(&file, stop, |header| {
+                if list {
+                    info!("{}", header);
+                }
+            })?;
+            inspect::log_inspection(&file, &inspection);
+            if let Some(e) = inspection.error {
+                anyhow::bail!("{} has invalid framing: {}", file.display(), e);
+            }
+        }
+        HintsCommand::Diff { a, b } => match inspect::diff(&a, &b)? {
+            inspect::DiffOutcome::Identical { hints } => {
+                info!("Identical: {} hints", hints);
+            }
+            inspect::DiffOutcome::Diverged(divergence) => {
+                anyhow::bail!("hints diverge — {}", divergence);
+            }
+        },
+        HintsCommand::Serve(args) => serve::run(args)?,
+    }
+    Ok(())
 }
 
 pub fn run(args: HintsGenArgs) -> Result<()> {
-    let client = create_client(args.client);
-
-    // Default to a per-client folder (e.g. reth-hints), mirroring input-gen's
-    // <client>-inputs convention, so each client's hints stay isolated.
-    let output_dir = args
-        .output_dir
-        .clone()
-        .unwrap_or_else(|| PathBuf::from(format!("{}-hints", client.name())));
-    std::fs::create_dir_all(&output_dir).with_context(|| {
-        format!(
-            "Failed to create output directory: {}",
-            output_dir.display()
-        )
-    })?;
+    let client: Arc<dyn ExecutionClient> = create_client(args.client).into();
+    let timeout = args.timeout.map(Duration::from_secs);
+
+    let output = match &args.hints_cache {
+        Some(root) => {
+            let cache = HintsCache::new(root, args.client)?;
+            info!("Hints cache: {}", cache.dir().display());
+            Output::Cache(cache)
+        }
+        None => {
+            // Default to a per-client folder (e.g. reth-hints), mirroring
+            // input-gen's <client>-inputs convention, so each client's hints
+            // stay isolated.
+            let output_dir = args
+                .output_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(format!("{}-hints", client.name())));
+            std::fs::create_dir_all(&output_dir).with_context(|| {
+                format!(
+                    "Failed to create output directory: {}",
+                    output_dir.display()
+                )
+            })?;
+            Output::Dir(output_dir)
+        }
+    };
 
     let inputs: Vec<PathBuf> = if let Some(folder) = &args.inputs_folder {
-        let mut entries: Vec<PathBuf> = std::fs::read_dir(folder)
-            .with_context(|| format!("Failed to read directory {}", folder.display()))?
-            .filter_map(|e| e.ok())
-            .map(|e| e.path())
-            .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("bin"))
-            .collect();
-        entries.sort();
-        entries
+        inputs::discover(folder, "bin", &args.filter)?
     } else if !args.inputs.is_empty() {
-        args.inputs.clone()
+        inputs::select(Path::new(""), args.inputs.clone(), &args.filter)?
     } else {
         anyhow::bail!("Provide at least one input file or use --inputs-folder");
     };
+    if matches!(output, Output::Dir(_)) {
+        inputs::check_unique_stems(&inputs)?;
+    }
 
     let mut failed: Vec<(&Path, anyhow::Error)> = Vec::new();
-    let mut timings: Vec<(Duration, Duration)> = Vec::new();
-    for input in &inputs {
-        match process_input_file(input, &output_dir, client.as_ref()) {
-            Ok(t) => timings.push(t),
-            Err(e) => {
-                error!("Failed {}: {:#}", input.display(), e);
-                failed.push((input.as_path(), e));
+    // A timed-out run can't be stopped and would taint the hints of every
+    // later input in this process, so with a timeout each input gets a worker
+    // process of its own.
+    if (args.jobs > 1 || timeout.is_some()) && inputs.len() > 1 {
+        let start = Instant::now();
+        let results = workers::run_workers(
+            &inputs,
+            &output,
+            args.client,
+            args.jobs,
+            args.check_determinism,
+            timeout,
+        )?;
+        let mut timings = Vec::with_capacity(results.len());
+        for (input, result) in inputs.iter().zip(results) {
+            match result {
+                Ok(t) => timings.push(t),
+                Err(e) => failed.push((input.as_path(), e)),
+            }
+        }
+        if !timings.is_empty() {
+            // Workers log their own execution times; only the wall time of each
+            // worker is known here.
+            info!(
+                "{} blocks in {:.2?} — average per block: {:.2?}",
+                timings.len(),
+                start.elapsed(),
+                timings.iter().sum::<Duration>() / timings.len() as u32,
+            );
+        }
+    } else {
+        let mut timings: Vec<(Duration, Duration)> = Vec::new();
+        for input in &inputs {
+            match process_input_file(input, &output, &client, args.check_determinism, timeout) {
+                Ok(t) => timings.extend(t),
+                Err(e) => {
+                    error!("Failed {}: {:#}", input.display(), e);
+                    failed.push((input.as_path(), e));
+                }
             }
         }
-    }
 
-    if timings.len() > 1 {
-        let n = timings.len() as u32;
-        let avg_execution = timings.iter().map(|(e, _)| *e).sum::<Duration>() / n;
-        let avg_total = timings.iter().map(|(_, t)| *t).sum::<Duration>() / n;
-        info!(
-            "Average over {} blocks — execution: {:.2?}, total: {:.2?}",
-            n, avg_execution, avg_total,
-        );
+        if timings.len() > 1 {
+            let n = timings.len() as u32;
+            let avg_execution = timings.iter().map(|(e, _)| *e).sum::<Duration>() / n;
+            let avg_total = timings.iter().map(|(_, t)| *t).sum::<Duration>() / n;
+            info!(
+                "Average over {} blocks — execution: {:.2?}, total: {:.2?}",
+                n, avg_execution, avg_total,
+            );
+        }
     }
 
-    if !failed.is_empty() {
-        warn!("{}/{} blocks failed:", failed.len(), inputs.len());
-        for (path, _) in &failed {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    warn!("{}/{} blocks failed:", failed.len(), inputs.len());
+    for (path, e) in &failed {
+        if is_timeout(e) {
+            warn!("  {} (timed out)", path.display());
+        } else {
             warn!("  {}", path.display());
         }
-        anyhow::bail!("{} block(s) failed hints generation", failed.len());
     }
+    let n = failed.len();
+    if failed.iter().all(|(_, e)| is_timeout(e)) {
+        // Keeps the HintsTimeout in the chain, for TIMEOUT_EXIT_CODE.
+        let (_, e) = failed.swap_remove(0);
+        return Err(e.context(format!("{n} block(s) timed out")));
+    }
+    anyhow::bail!("{} block(s) failed hints generation", n);
+}
 
-    Ok(())
+/// Whether `e` is, or was caused by, a hints-generation timeout.
+pub fn is_timeout(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<HintsTimeout>().is_some()
 }
 
+/// Where hints are written.
+enum Output {
+    /// `<dir>/<input stem>.hints`.
+    Dir(PathBuf),
+    Cache(HintsCache),
+}
+
+/// Generate `input`'s hints, returning the generation's timings, or `None`
+/// when they were already cached.
 fn process_input_file(
     input: &Path,
-    output_dir: &Path,
-    client: &dyn ExecutionClient,
-) -> Result<(Duration, Duration)> {
+    output: &Output,
+    client: &Arc<dyn ExecutionClient>,
+    check: bool,
+    timeout: Option<Duration>,
+) -> Result<Option<(Duration, Duration)>> {
     let stdin = ZiskStdin::from_file(input).context("Failed to load input file")?;
-    let stem = input
-        .file_stem()
-        .with_context(|| format!("Input path has no file stem: {}", input.display()))?;
-    let output_path = output_dir.join(stem).with_extension("hints");
+    let output_dir = match output {
+        Output::Dir(dir) => dir,
+        Output::Cache(cache) => {
+            return Ok(cache
+                .get_or_generate(input, &stdin, client, check, timeout)?
+                .generated);
+        }
+    };
+    let output_path = hints_path(output_dir, input)?;
     info!(
         "Generating hints: {} -> {}",
         input.display(),
         output_path.display()
     );
-    generate_hints_to_file(&stdin, output_path, client)
+    let timings = generate_hints_to_file(&stdin, output_path.clone(), timeout, client)?;
+    if check {
+        check_determinism(&stdin, &output_path, timeout, client)?;
+    }
+    Ok(Some(timings))
+}
+
+/// `<output_dir>/<input stem>.hints`.
+fn hints_path(output_dir: &Path, input: &Path) -> Result<PathBuf> {
+    let stem = input
+        .file_stem()
+        .with_context(|| format!("Input path has no file stem: {}", input.display()))?;
+    Ok(output_dir.join(stem).with_extension("hints"))
 }
+
+/// Generate `stdin`'s hints a second time and compare them with those just
+/// written to `hints_path`, failing at the first difference. The second run's
+/// hints are kept next to the first (`.hints.rerun`) when they differ.
+pub fn check_determinism(
+    stdin: &ZiskStdin,
+    hints_path: &Path,
+    timeout: Option<Duration>,
+    client: &Arc<dyn ExecutionClient>,
+) -> Result<()> {
+    // Generated under a name of this process's own, so concurrent checks of
+    // the same hints (a shared --hints-cache) don't write over each other.
+    let rerun = hints_path.with_extension(format!("hints.rerun.{}.partial", std::process::id()));
+    let outcome = generate_hints_to_file(stdin, rerun.clone(), timeout, client)
+        .context("Failed to regenerate hints for the determinism check")
+        .and_then(|_| inspect::diff(hints_path, &rerun));
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            let _ = std::fs::remove_file(&rerun);
+            return Err(e);
+        }
+    };
+
+    match outcome {
+        inspect::DiffOutcome::Identical { hints } => {
+            info!("Hints are deterministic ({} hints)", hints);
+            std::fs::remove_file(&rerun)
+                .with_context(|| format!("Failed to remove {}", rerun.display()))?;
+            Ok(())
+        }
+        inspect::DiffOutcome::Diverged(divergence) => {
+            let rerun_path = hints_path.with_extension("hints.rerun");
+            std::fs::rename(&rerun, &rerun_path)
+                .with_context(|| format!("Failed to move hints into {}", rerun_path.display()))?;
+            Err(NondeterministicHints {
+                divergence: divergence.to_string(),
+                offset: divergence.offset(),
+                rerun_path,
+            }
+            .into())
+        }
+    }
+}
+
+/// Two hints generations of the same input differed (`--check-determinism`).
+#[derive(Debug)]
+pub struct NondeterministicHints {
+    pub offset: u64,
+    pub divergence: String,
+    /// Where the second run's hints were kept.
+    pub rerun_path: PathBuf,
+}
+
+impl std::fmt::Display for NondeterministicHints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "nondeterministic hints: runs differ at byte offset {} — {}; second run kept in {}",
+            self.offset,
+            self.divergence,
+            self.rerun_path.display()
+        )
+    }
+}
+
+impl std::error::Error for NondeterministicHints {}
@@ -0,0 +1,510 @@
+//! `hints-gen serve`: a long-lived hints generator for real-time proving.
+//!
+//! Listens on a Unix socket or a TCP port and generates hints for one block at
+//! a time, keeping the client — and the crypto providers its `run()` installs
+//! once per process — warm between blocks. Connections are served one after
+//! another: hints generation is process-global (see `workers`).
+//!
+//! The protocol is newline-delimited JSON. Each [`Request`] line is followed by
+//! `input_len` raw bytes of an input `.bin` when it carries one, and is answered
+//! by one [`Response`] line — preceded by a `listening` one when the hints are
+//! streamed to a socket, so the prover knows when to connect.
+//!
+//! The protocol has no authentication, so what a request can reach is kept
+//! small: sinks are created inside the operator's `--sink-dir` only, inputs
+//! are capped by `--max-input-len`, and `--tcp` takes a loopback address
+//! unless `--allow-remote` is given. A timed-out run can't be stopped and
+//! would taint every later run in the process, so the server exits after
+//! answering it, for a supervisor to restart.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener},
+    os::unix::{fs::FileTypeExt, net::UnixListener},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+use tracing::{error, info};
+use zisk_sdk::ZiskStdin;
+
+use input::{
+    Client, ExecutionClient, RpcConfig, create_client, generate_hints_to_file,
+    generate_hints_to_socket, parse_header, stdin_slices,
+};
+
+use super::is_timeout;
+
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    /// Unix socket to listen on
+    #[arg(long, required_unless_present = "tcp", conflicts_with = "tcp")]
+    socket: Option<PathBuf>,
+
+    /// TCP address to listen on (e.g. 127.0.0.1:7800); loopback only, unless
+    /// --allow-remote
+    #[arg(long)]
+    tcp: Option<SocketAddr>,
+
+    /// Accept a non-loopback --tcp address. Anyone who can connect can run
+    /// hints generation: the protocol has no authentication
+    #[arg(long, requires = "tcp")]
+    allow_remote: bool,
+
+    /// Directory the requests' `output` files and `socket`s are created in.
+    /// Requests name them relative to it; other paths are refused
+    #[arg(long, value_name = "DIR")]
+    sink_dir: PathBuf,
+
+    /// Largest input a request may send, in bytes. A larger `input_len` is
+    /// answered with an error and ends the connection
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_INPUT_LEN)]
+    max_input_len: u64,
+
+    /// Execution client
+    #[arg(short, long, value_enum, default_value = "reth")]
+    client: Client,
+
+    /// RPC URL for requests that name a block instead of sending its input
+    #[arg(short = 'u', long)]
+    rpc_url: Option<String>,
+
+    /// Optional RPC headers (format: "Key:Value", repeatable). Only honored by
+    /// clients that support custom HTTP headers (reth).
+    #[arg(short = 'H', long, value_parser = parse_header, requires = "rpc_url")]
+    rpc_headers: Vec<(String, String)>,
+
+    /// Fail a request whose hints generation runs longer than this many
+    /// seconds. The server then exits (code 124) after answering it
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+}
+
+/// Default `--max-input-len`: 1 GiB, well above any block's input.
+pub const DEFAULT_MAX_INPUT_LEN: u64 = 1 << 30;
+
+/// What a [`Server`] accepts, besides its client and RPC.
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    /// Where request sinks are created; see `--sink-dir`.
+    pub sink_dir: PathBuf,
+    pub max_input_len: u64,
+    pub timeout: Option<Duration>,
+}
+
+/// One block to generate hints for. The input is either `input_len` bytes of
+/// an input `.bin` following the request line, or `block`, fetched over the
+/// server's RPC. The hints go to `output` (a file) or `socket` (a Unix socket
+/// the prover connects to), exactly one of them, named relative to the
+/// server's `--sink-dir`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Request {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_len: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The hints socket is listening; the final response follows.
+    Listening,
+    Ok,
+    Error,
+    /// The hints generation outlived the server's `--timeout`.
+    TimedOut,
+}
+
+/// The answer to a [`Request`]. Times are in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub status: Status,
+    /// Time to fetch the block's input (`block` requests).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_time: Option<f64>,
+    /// Native execution time of the hints generation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_time: Option<f64>,
+    /// Hints generation including sink setup and teardown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_time: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn status(status: Status) -> Self {
+        Self {
+            status,
+            fetch_time: None,
+            execution_time: None,
+            total_time: None,
+            error: None,
+        }
+    }
+
+    fn error(e: &anyhow::Error) -> Self {
+        let status = if is_timeout(e) {
+            Status::TimedOut
+        } else {
+            Status::Error
+        };
+        Self {
+            error: Some(format!("{e:#}")),
+            ..Self::status(status)
+        }
+    }
+}
+
+pub fn run(args: ServeArgs) -> Result<()> {
+    if let Some(addr) = args.tcp
+        && !addr.ip().is_loopback()
+        && !args.allow_remote
+    {
+        anyhow::bail!(
+            "{addr} is not a loopback address; the protocol has no authentication, \
+             so pass --allow-remote to serve it anyway"
+        );
+    }
+    std::fs::create_dir_all(&args.sink_dir)
+        .with_context(|| format!("Failed to create the sink dir {}", args.sink_dir.display()))?;
+    let rpc = args
+        .rpc_url
+        .map(|url| RpcConfig::new(url).with_headers(args.rpc_headers));
+    let server = Server::new(
+        args.client,
+        rpc,
+        ServeOptions {
+            sink_dir: args.sink_dir,
+            max_input_len: args.max_input_len,
+            timeout: args.timeout.map(Duration::from_secs),
+        },
+    )?;
+
+    if let Some(path) = args.socket {
+        // A socket left behind by a previous server would make binding fail.
+        remove_stale_socket(&path)?;
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+        info!(
+            "Serving {} hints on {}",
+            server.client.display_name(),
+            path.display()
+        );
+        for stream in listener.incoming() {
+            server.serve_logged(&stream.context("Failed to accept a connection")?)?;
+        }
+    } else if let Some(addr) = args.tcp {
+        let listener =
+            TcpListener::bind(addr).with_context(|| format!("Failed to listen on {addr}"))?;
+        info!(
+            "Serving {} hints on {}",
+            server.client.display_name(),
+            listener.local_addr()?
+        );
+        for stream in listener.incoming() {
+            server.serve_logged(&stream.context("Failed to accept a connection")?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Generates hints for the requests of one connection at a time.
+pub struct Server {
+    client: Arc<dyn ExecutionClient>,
+    rpc: Option<RpcConfig>,
+    options: ServeOptions,
+    /// Drives `from_rpc` for `block` requests.
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Server {
+    pub fn new(client: Client, rpc: Option<RpcConfig>, options: ServeOptions) -> Result<Self> {
+        Self::with_client(create_client(client).into(), rpc, options)
+    }
+
+    pub fn with_client(
+        client: Arc<dyn ExecutionClient>,
+        rpc: Option<RpcConfig>,
+        options: ServeOptions,
+    ) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to start the RPC runtime")?;
+        Ok(Self {
+            client,
+            rpc,
+            options,
+            runtime,
+        })
+    }
+
+    /// Serve one connection, logging how it failed. Only a timeout is
+    /// returned: the server can't go on after one.
+    fn serve_logged<S>(&self, stream: &S) -> Result<()>
+    where
+        for<'a> &'a S: Read + Write,
+        S: Sync,
+    {
+        match self.serve_connection(stream) {
+            Err(e) if is_timeout(&e) => {
+                error!("Hints generation timed out; exiting for a restart");
+                Err(e)
+            }
+            Err(e) => {
+                error!("Connection failed: {:#}", e);
+                Ok(())
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// Answer `stream`'s requests until it closes. A failed request is
+    /// answered with an error and the connection stays open; a broken
+    /// connection, an unreadable or oversized request, or a timeout ends it.
+    /// After a timeout the server must not serve again (see the module docs).
+    pub fn serve_connection<S>(&self, stream: &S) -> Result<()>
+    where
+        for<'a> &'a S: Read + Write,
+        S: Sync,
+    {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request: Request = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    // Without the request, any payload that follows can't be
+                    // skipped, so the connection can't continue.
+                    let e = anyhow::Error::new(e).context("Invalid request");
+                    write_response(stream, &Response::error(&e))?;
+                    return Err(e);
+                }
+            };
+
+            let payload = match request.input_len {
+                Some(len) if len > self.options.max_input_len => {
+                    // The payload isn't read, so the connection can't continue.
+                    let e = anyhow::anyhow!(
+                        "input_len {len} is over the server's --max-input-len {}",
+                        self.options.max_input_len
+                    );
+                    write_response(stream, &Response::error(&e))?;
+                    return Err(e);
+                }
+                Some(len) => {
+                    let mut payload = vec![0; len as usize];
+                    reader
+                        .read_exact(&mut payload)
+                        .context("Connection closed before the input was sent")?;
+                    Some(payload)
+                }
+                None => None,
+            };
+
+            let response = match self.handle(stream, &request, payload) {
+                Ok(response) => response,
+                Err(e) if is_timeout(&e) => {
+                    write_response(stream, &Response::error(&e))?;
+                    return Err(e);
+                }
+                Err(e) => {
+                    error!("Request failed: {:#}", e);
+                    Response::error(&e)
+                }
+            };
+            write_response(stream, &response)?;
+        }
+    }
+
+    fn handle<S>(&self, stream: &S, request: &Request, payload: Option<Vec<u8>>) -> Result<Response>
+    where
+        for<'a> &'a S: Read + Write,
+        S: Sync,
+    {
+        let mut fetch_time = None;
+        let stdin = match (payload, request.block) {
+            (Some(payload), None) => {
+                let stdin = ZiskStdin::new();
+                for slice in stdin_slices(&payload)? {
+                    stdin.write_slice(slice);
+                }
+                stdin
+            }
+            (None, Some(block)) => {
+                let rpc = self
+                    .rpc
+                    .as_ref()
+                    .context("block requests need the server to be started with --rpc-url")?;
+                let start = Instant::now();
+                let (stdin, _) = self
+                    .runtime
+                    .block_on(self.client.from_rpc(rpc, block))
+                    .with_context(|| format!("Failed to fetch block {block}"))?;
+                fetch_time = Some(start.elapsed().as_secs_f64());
+                stdin
+            }
+            _ => anyhow::bail!("a request needs exactly one of `input_len` and `block`"),
+        };
+
+        let timeout = self.options.timeout;
+        let (execution, total) = match (&request.output, &request.socket) {
+            (Some(output), None) => {
+                let output = self.sink_path(output)?;
+                generate_hints_to_file(&stdin, output, timeout, &self.client)?
+            }
+            (None, Some(socket)) => {
+                let socket = self.sink_path(socket)?;
+                self.generate_to_socket(stream, &stdin, socket)?
+            }
+            _ => anyhow::bail!("a request needs exactly one of `output` and `socket`"),
+        };
+        Ok(Response {
+            fetch_time,
+            execution_time: Some(execution.as_secs_f64()),
+            total_time: Some(total.as_secs_f64()),
+            ..Response::status(Status::Ok)
+        })
+    }
+
+    /// `requested` inside `--sink-dir`: a plain relative path, without `..`.
+    fn sink_path(&self, requested: &Path) -> Result<PathBuf> {
+        confine(&self.options.sink_dir, requested)
+    }
+
+    /// Stream the hints to `socket`, telling the requester once it listens.
+    fn generate_to_socket<S>(
+        &self,
+        stream: &S,
+        stdin: &ZiskStdin,
+        socket: PathBuf,
+    ) -> Result<(Duration, Duration)>
+    where
+        for<'a> &'a S: Read + Write,
+        S: Sync,
+    {
+        remove_stale_socket(&socket)?;
+        let (ready_tx, ready_rx) = oneshot::channel();
+        std::thread::scope(|scope| {
+            let notify = scope.spawn(|| match ready_rx.blocking_recv() {
+                Ok(()) => write_response(stream, &Response::status(Status::Listening)),
+                // The generator ended before listening; its error is returned.
+                Err(_) => Ok(()),
+            });
+            let result = generate_hints_to_socket(
+                stdin,
+                socket.clone(),
+                None,
+                None,
+                Some(ready_tx),
+                self.options.timeout,
+                &self.client,
+            );
+            // Joined first so `listening` always precedes the final response.
+            notify.join().expect("notifier panicked")?;
+            remove_stale_socket(&socket)?;
+            result
+        })
+    }
+}
+
+/// `requested` under `dir`, when it is a plain relative path: no root, no
+/// `..`, nothing that could lead out of `dir`.
+fn confine(dir: &Path, requested: &Path) -> Result<PathBuf> {
+    let plain = requested.components().next().is_some()
+        && requested
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if !plain {
+        anyhow::bail!(
+            "{} is not a path relative to the server's --sink-dir",
+            requested.display()
+        );
+    }
+    Ok(dir.join(requested))
+}
+
+/// Remove the socket at `path`, if there is one. Anything else there is left
+/// alone, and binding to it fails.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove the stale socket {}", path.display())),
+        _ => Ok(()),
+    }
+}
+
+fn write_response<S>(stream: &S, response: &Response) -> Result<()>
+where
+    for<'a> &'a S: Write,
+{
+    let mut line = serde_json::to_string(response)?;
+    line.push('\n');
+    let mut stream = stream;
+    stream
+        .write_all(line.as_bytes())
+        .context("Failed to send the response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sinks_are_confined_to_the_sink_dir() {
+        let dir = Path::new("/srv/hints");
+        assert_eq!(
+            confine(dir, Path::new("a.hints")).unwrap(),
+            dir.join("a.hints")
+        );
+        assert_eq!(
+            confine(dir, Path::new("blocks/a.hints")).unwrap(),
+            dir.join("blocks/a.hints")
+        );
+        for requested in [
+            "",
+            "/etc/passwd",
+            "../a.hints",
+            "blocks/../../a.hints",
+            "./a.hints",
+        ] {
+            assert!(confine(dir, Path::new(requested)).is_err(), "{requested}");
+        }
+    }
+
+    #[test]
+    fn only_sockets_are_removed() {
+        let dir = std::env::temp_dir().join(format!("serve-stale-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let file = dir.join("not-a-socket");
+        std::fs::write(&file, b"keep").unwrap();
+        remove_stale_socket(&file).unwrap();
+        assert!(file.exists());
+
+        let socket = dir.join("stale.sock");
+        drop(UnixListener::bind(&socket).unwrap());
+        remove_stale_socket(&socket).unwrap();
+        assert!(!socket.exists());
+
+        remove_stale_socket(&dir.join("missing")).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+//! `--jobs`: generate hints for several inputs at once, one worker process per
+//! input.
+//!
+//! Hints generation can't be parallelized within one process: the native input
+//! (`ziskos::set_native_input`) and the hints sink (`ZISK_HINTS_OUTPUT`, read by
+//! `zkvm_init`) are process-global, and the run is pinned to a one-thread pool
+//! so the hints come out in a deterministic order. Each worker is this same
+//! executable, re-invoked on a single input, so it takes exactly the serial path
+//! and its hints file is byte-identical to a serial run's.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+use input::{Client, HintsTimeout};
+
+use super::{Output, TIMEOUT_EXIT_CODE};
+
+/// How long past its runs' timeouts a worker gets to load its input and
+/// report the timeout itself before it is killed.
+const KILL_GRACE: Duration = Duration::from_secs(30);
+
+/// How often a worker with a timeout is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run one worker per input, at most `jobs` at a time. Returns each input's
+/// wall time, or why its worker failed, in input order.
+pub(super) fn run_workers(
+    inputs: &[PathBuf],
+    output: &Output,
+    client: Client,
+    jobs: usize,
+    check_determinism: bool,
+    timeout: Option<Duration>,
+) -> Result<Vec<Result<Duration>>> {
+    let exe = std::env::current_exe().context("Failed to locate the hints-gen executable")?;
+    let client = client
+        .to_possible_value()
+        .expect("clients are never skipped")
+        .get_name()
+        .to_string();
+    info!(
+        "Generating hints for {} inputs with {} worker processes",
+        inputs.len(),
+        jobs
+    );
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..inputs.len()).map(|_| None).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(inputs.len()) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(input) = inputs.get(index) else {
+                        break;
+                    };
+                    let result =
+                        run_worker(&exe, input, output, &client, check_determinism, timeout);
+                    if let Err(e) = &result {
+                        error!("Failed {}: {:#}", input.display(), e);
+                    }
+                    results.lock().expect("results lock poisoned")[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    Ok(results
+        .into_inner()
+        .expect("results lock poisoned")
+        .into_iter()
+        .map(|result| result.expect("every input is claimed by a worker"))
+        .collect())
+}
+
+fn run_worker(
+    exe: &Path,
+    input: &Path,
+    output: &Output,
+    client: &str,
+    check_determinism: bool,
+    timeout: Option<Duration>,
+) -> Result<Duration> {
+    let start = Instant::now();
+    let mut command = Command::new(exe);
+    command.arg("--client").arg(client);
+    match output {
+        Output::Dir(dir) => command.arg("--output-dir").arg(dir),
+        // The worker derives the same client and ELF subdirectory.
+        Output::Cache(cache) => command.arg("--hints-cache").arg(cache.root()),
+    };
+    if check_determinism {
+        command.arg("--check-determinism");
+    }
+    // The worker enforces the timeout itself, removing its partial hints
+    // file, and reports it through its exit code. Should it hang past
+    // that anyway, it is killed once its runs' timeouts plus a grace period
+    // have passed.
+    if let Some(timeout) = timeout {
+        command.arg("--timeout").arg(timeout.as_secs().to_string());
+    }
+    let mut child = command
+        .arg(input)
+        .spawn()
+        .with_context(|| format!("Failed to start a worker for {}", input.display()))?;
+    let status = match timeout {
+        Some(timeout) => {
+            let runs = if check_determinism { 2 } else { 1 };
+            let deadline = start + timeout * runs + KILL_GRACE;
+            let Some(status) = wait_or_kill(&mut child, deadline)? else {
+                if let Output::Dir(dir) = output {
+                    // A killed worker leaves its hints file half-written.
+                    let partial = super::hints_path(dir, input)?;
+                    if let Err(e) = std::fs::remove_file(&partial)
+                        && e.kind() != std::io::ErrorKind::NotFound
+                    {
+                        warn!("Failed to remove {}: {}", partial.display(), e);
+                    }
+                }
+                return Err(anyhow::Error::new(HintsTimeout(timeout))
+                    .context("worker didn't exit after its timeout and was killed"));
+            };
+            status
+        }
+        None => child.wait()?,
+    };
+    if let Some(timeout) = timeout
+        && status.code() == Some(TIMEOUT_EXIT_CODE)
+    {
+        return Err(HintsTimeout(timeout).into());
+    }
+    if !status.success() {
+        anyhow::bail!("worker exited with {status}");
+    }
+    Ok(start.elapsed())
+}
+
+/// Wait for `child` until `deadline`, then kill it. `None` when it was killed.
+fn wait_or_kill(child: &mut Child, deadline: Instant) -> Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            child.kill().context("Failed to kill a hung worker")?;
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_worker_past_its_deadline_is_killed() {
+        let mut child = Command::new("sleep").arg("60").spawn().unwrap();
+        let start = Instant::now();
+        let status = wait_or_kill(&mut child, start + Duration::from_millis(200)).unwrap();
+        assert!(status.is_none());
+        assert!(start.elapsed() < Duration::from_secs(10));
+        // Reaped: there's nothing left to wait for.
+        assert!(child.try_wait().unwrap().is_some());
+    }
+
+    #[test]
+    fn a_worker_that_exits_in_time_keeps_its_status() {
+        let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let status = wait_or_kill(&mut child, deadline).unwrap().unwrap();
+        assert_eq!(status.code(), Some(3));
+    }
+}
@@ -0,0 +1,369 @@
+//! Input discovery shared by the benchmark runner and hints-gen: a recursive
+//! walk filtered by extension, glob include/exclude patterns, and the block
+//! metadata encoded in input filenames
+//! (`<chain>_<block>_<txs>_<mgas>_zec_<client>.<ext>`).
+
+use anyhow::{Context, Result};
+use clap::Args;
+use glob::Pattern;
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use input::InputFileName;
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct InputFilter {
+    /// Include only inputs whose path matches this glob (or, without glob
+    /// characters, whose full path contains this string). Repeatable
+    #[arg(long)]
+    pub include: Vec<String>,
+
+    /// Exclude inputs whose path matches this glob (or, without glob
+    /// characters, whose full path contains this string). Repeatable
+    #[arg(long)]
+    pub exclude: Vec<String>,
+
+    /// Only inputs with at least this much gas used, in Mgas (from the filename)
+    #[arg(long)]
+    pub min_gas: Option<u64>,
+
+    /// Only inputs for blocks in this range: `A..B` (end exclusive), `A..=B`
+    /// or a single block number (from the filename)
+    #[arg(long, value_parser = parse_block_range)]
+    pub blocks: Option<RangeInclusive<u64>>,
+
+    /// Only inputs for this chain, e.g. `mainnet` or `sepolia` (from the filename)
+    #[arg(long)]
+    pub chain: Option<String>,
+}
+
+impl InputFilter {
+    fn filters_metadata(&self) -> bool {
+        self.min_gas.is_some() || self.blocks.is_some() || self.chain.is_some()
+    }
+
+    fn log(&self) {
+        if !self.include.is_empty() {
+            info!("Include patterns: {:?}", self.include);
+        }
+        if !self.exclude.is_empty() {
+            info!("Exclude patterns: {:?}", self.exclude);
+        }
+        if let Some(min_gas) = self.min_gas {
+            info!("Minimum gas: {} Mgas", min_gas);
+        }
+        if let Some(blocks) = &self.blocks {
+            info!("Blocks: {}..={}", blocks.start(), blocks.end());
+        }
+        if let Some(chain) = &self.chain {
+            info!("Chain: {}", chain);
+        }
+    }
+}
+
+/// Find the inputs under `root` with the given extension, recursively and in
+/// sorted order, keeping those `filter` selects. A `root` that is a file is
+/// the only candidate, whatever its extension, and is filtered like the rest.
+pub fn discover(root: &Path, extension: &str, filter: &InputFilter) -> Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        let parent = root.parent().unwrap_or(Path::new(""));
+        return select(parent, vec![root.to_path_buf()], filter);
+    }
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(root).follow_links(true).sort_by_file_name() {
+        let entry = entry.with_context(|| format!("Failed to walk {}", root.display()))?;
+        let path = entry.path();
+        if entry.file_type().is_file() && path.extension().is_some_and(|ext| ext == extension) {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    select(root, files, filter)
+}
+
+/// Keep the `files` (found under `root`) that `filter` selects.
+pub fn select(root: &Path, mut files: Vec<PathBuf>, filter: &InputFilter) -> Result<Vec<PathBuf>> {
+    filter.log();
+    let include = compile(&filter.include)?;
+    let exclude = compile(&filter.exclude)?;
+    let chain = filter.chain.as_ref().map(|c| c.to_lowercase());
+
+    let mut unnamed = 0usize;
+    files.retain(|file| {
+        let relative = file.strip_prefix(root).unwrap_or(file);
+        if !include.is_empty() && !include.iter().any(|m| m.matches(file, relative)) {
+            return false;
+        }
+        if exclude.iter().any(|m| m.matches(file, relative)) {
+            return false;
+        }
+        if !filter.filters_metadata() {
+            return true;
+        }
+
+        let Some(name) = file
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(InputFileName::parse)
+        else {
+            unnamed += 1;
+            return false;
+        };
+        filter.min_gas.is_none_or(|min| name.mgas >= min)
+            && filter
+                .blocks
+                .as_ref()
+                .is_none_or(|blocks| blocks.contains(&name.block_number))
+            && chain.as_ref().is_none_or(|chain| name.chain == *chain)
+    });
+
+    if unnamed > 0 {
+        warn!(
+            "Skipped {} input(s) whose filename doesn't carry block metadata \
+             (`<chain>_<block>_<txs>_<mgas>_zec_<client>`)",
+            unnamed
+        );
+    }
+    Ok(files)
+}
+
+/// Fail when two of `files` share a file stem: their hints would be written
+/// to the same `<stem>.hints` in a flat output folder.
+pub fn check_unique_stems(files: &[PathBuf]) -> Result<()> {
+    let mut seen: HashMap<&OsStr, &Path> = HashMap::new();
+    for file in files {
+        let Some(stem) = file.file_stem() else {
+            continue;
+        };
+        if let Some(first) = seen.insert(stem, file) {
+            anyhow::bail!(
+                "{} and {} would write the same hints file; narrow the inputs with \
+                 --include/--exclude or use --hints-cache",
+                first.display(),
+                file.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// An include/exclude pattern: a glob, or a plain substring when it has no
+/// glob characters (the original `--include`/`--exclude` behavior, which
+/// matched the whole path, input folder included).
+enum Matcher {
+    Glob(Pattern),
+    Contains(String),
+}
+
+impl Matcher {
+    /// Globs match the path relative to the input root, or the file name;
+    /// substrings match the full path of `file`.
+    fn matches(&self, file: &Path, relative: &Path) -> bool {
+        match self {
+            Self::Glob(pattern) => {
+                pattern.matches_path(relative)
+                    || relative
+                        .file_name()
+                        .is_some_and(|name| pattern.matches_path(Path::new(name)))
+            }
+            Self::Contains(s) => file.to_string_lossy().contains(s.as_str()),
+        }
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Matcher>> {
+    patterns
+        .iter()
+        .map(|p| {
+            if p.contains(['*', '?', '[']) {
+                Pattern::new(p)
+                    .map(Matcher::Glob)
+                    .with_context(|| format!("Invalid glob pattern: {p}"))
+            } else {
+                Ok(Matcher::Contains(p.clone()))
+            }
+        })
+        .collect()
+}
+
+fn parse_block_range(s: &str) -> Result<RangeInclusive<u64>> {
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<u64>()
+            .with_context(|| format!("Invalid block number `{n}` in range `{s}`"))
+    };
+    let range = if let Some((start, end)) = s.split_once("..=") {
+        parse(start)?..=parse(end)?
+    } else if let Some((start, end)) = s.split_once("..") {
+        let end = parse(end)?;
+        if end == 0 {
+            anyhow::bail!("Empty block range `{s}`");
+        }
+        parse(start)?..=end - 1
+    } else {
+        let block = parse(s)?;
+        block..=block
+    };
+    if range.is_empty() {
+        anyhow::bail!("Empty block range `{s}`");
+    }
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_ranges() {
+        let cases = [
+            ("100", 100..=100),
+            ("100..105", 100..=104),
+            ("100..=105", 100..=105),
+            (" 100 ..= 105 ", 100..=105),
+            ("0..1", 0..=0),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(parse_block_range(s).unwrap(), expected, "{s}");
+        }
+    }
+
+    #[test]
+    fn reversed_or_empty_block_ranges_are_rejected() {
+        for s in ["105..100", "105..=100", "100..100", "5..0"] {
+            let error = parse_block_range(s).unwrap_err().to_string();
+            assert!(error.contains("Empty block range"), "{s}: {error}");
+        }
+    }
+
+    #[test]
+    fn malformed_block_ranges_are_rejected() {
+        for s in [
+            "",
+            "abc",
+            "100..",
+            "..100",
+            "1..x",
+            "1...5",
+            "-1..5",
+            "1..=5..=6",
+        ] {
+            let error = parse_block_range(s).unwrap_err().to_string();
+            assert!(error.contains("Invalid block number"), "{s}: {error}");
+        }
+    }
+
+    #[test]
+    fn substring_patterns_match_the_full_path() {
+        let root = Path::new("inputs/mainnet");
+        let files = vec![
+            root.join("a/mainnet_1_10_5_zec_reth.bin"),
+            root.join("b/sepolia_2_10_5_zec_reth.bin"),
+        ];
+        let filter = |include: &str| InputFilter {
+            include: vec![include.to_string()],
+            ..Default::default()
+        };
+
+        // The input folder is part of the matched path, as before globs.
+        assert_eq!(
+            select(root, files.clone(), &filter("inputs/mainnet")).unwrap(),
+            files
+        );
+        assert_eq!(
+            select(root, files.clone(), &filter("sepolia")).unwrap(),
+            files[1..].to_vec()
+        );
+        // Globs stay relative to the input folder.
+        assert_eq!(
+            select(root, files.clone(), &filter("a/*.bin")).unwrap(),
+            files[..1].to_vec()
+        );
+    }
+
+    #[test]
+    fn metadata_filters_read_the_file_name() {
+        let root = Path::new("inputs");
+        let files = vec![
+            root.join("mainnet_100_10_5_zec_reth.bin"),
+            root.join("mainnet_101_10_30_zec_reth.bin"),
+            root.join("sepolia_100_10_30_zec_reth.bin"),
+            root.join("block_100.bin"),
+        ];
+        let select = |filter: InputFilter| select(root, files.clone(), &filter).unwrap();
+
+        let filter = InputFilter {
+            min_gas: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(select(filter), files[1..3].to_vec());
+
+        let filter = InputFilter {
+            blocks: Some(100..=100),
+            ..Default::default()
+        };
+        assert_eq!(select(filter), [files[0].clone(), files[2].clone()]);
+
+        let filter = InputFilter {
+            chain: Some("Sepolia".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(select(filter), files[2..3].to_vec());
+
+        // Filters combine, and unnamed inputs are dropped once any is set.
+        let filter = InputFilter {
+            chain: Some("mainnet".to_string()),
+            min_gas: Some(10),
+            blocks: Some(0..=u64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(select(filter), files[1..2].to_vec());
+
+        // Without metadata filters, unnamed inputs are kept.
+        assert_eq!(select(InputFilter::default()), files);
+    }
+
+    #[test]
+    fn a_file_root_is_filtered_too() {
+        let file = Path::new("inputs/mainnet_100_10_5_zec_reth.bin");
+        let filter = |chain: &str| InputFilter {
+            chain: Some(chain.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(discover(file, "bin", &filter("mainnet")).unwrap(), [file]);
+        assert!(
+            discover(file, "bin", &filter("sepolia"))
+                .unwrap()
+                .is_empty()
+        );
+
+        let exclude = InputFilter {
+            exclude: vec!["mainnet_*".to_string()],
+            ..Default::default()
+        };
+        assert!(discover(file, "bin", &exclude).unwrap().is_empty());
+    }
+
+    #[test]
+    fn same_stems_in_subfolders_are_rejected() {
+        let unique = [
+            PathBuf::from("a/mainnet_1_1_1_zec_reth.bin"),
+            PathBuf::from("a/mainnet_2_1_1_zec_reth.bin"),
+        ];
+        check_unique_stems(&unique).unwrap();
+
+        let clashing = [
+            unique[0].clone(),
+            PathBuf::from("b/mainnet_1_1_1_zec_reth.bin"),
+        ];
+        let error = check_unique_stems(&clashing).unwrap_err().to_string();
+        assert!(error.contains("b/mainnet_1_1_1_zec_reth.bin"), "{error}");
+    }
+}
@@ -1,5 +1,16 @@
 pub mod benchmark;
 pub mod cli;
+pub mod compare;
+pub mod differential;
 pub mod elfs;
+pub mod failure;
+pub mod hints_cache;
 pub mod hints_gen;
+pub mod inputs;
+pub mod limits;
+pub mod metadata;
+pub mod report;
+pub mod stream;
+pub mod system;
+pub mod verify;
 pub mod zisk;
@@ -0,0 +1,157 @@
+//! `--steps-threshold` and `--timeout`: per-input guards, so one pathological input
+//! can't hold up a whole run. An input over either limit is reported as
+//! `limit_exceeded`, apart from the inputs that failed.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::zisk::{ZiskExecutionMetrics, group_thousands};
+
+/// The limits every run of a benchmark is held to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// Most ZisK steps a run may report. The run isn't capped: it is checked
+    /// once it completes.
+    pub steps_threshold: Option<u64>,
+    /// Longest a run may take, from the start of the ZisK call.
+    pub timeout: Option<Duration>,
+}
+
+impl Limits {
+    /// Fail a run that took more steps than `--steps-threshold`, keeping its
+    /// metrics.
+    pub fn check_steps(&self, metrics: &ZiskExecutionMetrics) -> Result<(), LimitExceeded> {
+        match self.steps_threshold {
+            Some(max) if metrics.steps > max => Err(LimitExceeded {
+                limit: Limit::StepsThreshold(max),
+                metrics: Some(metrics.clone()),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Which limit a run exceeded, with its configured value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    /// `--steps-threshold`.
+    StepsThreshold(u64),
+    /// `--timeout`, in seconds.
+    Timeout(u64),
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StepsThreshold(_) => write!(f, "steps threshold"),
+            Self::Timeout(_) => write!(f, "timeout"),
+        }
+    }
+}
+
+/// A run stopped or rejected by a [`Limit`]. `metrics` are those of the run
+/// when it finished over the limit; a timed-out run has none.
+#[derive(Debug)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub metrics: Option<ZiskExecutionMetrics>,
+}
+
+impl LimitExceeded {
+    pub fn timeout(timeout: Duration) -> Self {
+        Self {
+            limit: Limit::Timeout(timeout.as_secs()),
+            metrics: None,
+        }
+    }
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            Limit::StepsThreshold(max) => {
+                write!(
+                    f,
+                    "run went over --steps-threshold {}",
+                    group_thousands(max)
+                )?;
+                if let Some(metrics) = &self.metrics {
+                    write!(f, " ({} steps)", group_thousands(metrics.steps))?;
+                }
+                Ok(())
+            }
+            Limit::Timeout(secs) => write!(f, "run did not finish within --timeout {secs}s"),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(steps: u64) -> ZiskExecutionMetrics {
+        ZiskExecutionMetrics {
+            duration: Duration::from_secs(1),
+            steps,
+            cost: None,
+            tx_count: None,
+            gas_used: None,
+            chain: None,
+            block_number: None,
+            committed_block_hash: None,
+        }
+    }
+
+    #[test]
+    fn check_steps_fails_only_over_the_threshold() {
+        let limits = Limits {
+            steps_threshold: Some(1_000),
+            timeout: None,
+        };
+        assert!(limits.check_steps(&metrics(999)).is_ok());
+        assert!(limits.check_steps(&metrics(1_000)).is_ok());
+
+        let exceeded = limits.check_steps(&metrics(1_001)).unwrap_err();
+        assert_eq!(exceeded.limit, Limit::StepsThreshold(1_000));
+        assert_eq!(exceeded.metrics.as_ref().map(|m| m.steps), Some(1_001));
+        assert_eq!(
+            exceeded.to_string(),
+            "run went over --steps-threshold 1,000 (1,001 steps)"
+        );
+    }
+
+    #[test]
+    fn check_steps_passes_without_a_threshold() {
+        let limits = Limits {
+            steps_threshold: None,
+            timeout: Some(Duration::from_secs(5)),
+        };
+        assert!(limits.check_steps(&metrics(u64::MAX)).is_ok());
+    }
+
+    #[test]
+    fn timeout_records_its_seconds_and_no_metrics() {
+        let exceeded = LimitExceeded::timeout(Duration::from_secs(30));
+        assert_eq!(exceeded.limit, Limit::Timeout(30));
+        assert!(exceeded.metrics.is_none());
+        assert_eq!(
+            exceeded.to_string(),
+            "run did not finish within --timeout 30s"
+        );
+    }
+
+    #[test]
+    fn limits_serialize_by_flag() {
+        assert_eq!(
+            serde_json::to_string(&Limit::StepsThreshold(500)).unwrap(),
+            r#"{"steps_threshold":500}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Limit::Timeout(30)).unwrap(),
+            r#"{"timeout":30}"#
+        );
+    }
+}
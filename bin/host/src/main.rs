@@ -1,15 +1,19 @@
 use anyhow::Result;
 use clap::Parser;
-use std::{fs::File, io::Write};
+use std::{fs::File, io::Write, time::Duration};
 use tracing::info;
 use zisk_sdk::VerboseMode;
 
 use host::benchmark::BenchmarkRunner;
 use host::cli::{Cli, GuestProgramCommand};
-#[cfg(feature = "ziskethone")]
-use host::elfs::ELF_ZISKETHONE;
-use host::elfs::{ELF_ETHREX, ELF_RETH};
-use input::Client;
+use host::compare::{self, CompareOptions};
+use host::differential::{self, DifferentialOptions};
+use host::elfs::guest_program;
+use host::limits::Limits;
+use host::metadata::RunMetadata;
+use host::verify;
+use host::zisk::{ZiskClient, format_hash, parse_hash};
+use input::{RpcConfig, create_client};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -27,6 +31,12 @@ async fn main() -> Result<()> {
     }
     info!(" Action: {:?}", cli.action);
     info!(" Guest Program: {}", cli.guest_program.display_name());
+    if let Some(steps) = cli.steps_threshold {
+        info!(" Steps Threshold: {}", steps);
+    }
+    if let Some(secs) = cli.timeout {
+        info!(" Timeout: {}s", secs);
+    }
 
     match &cli.guest_program {
         GuestProgramCommand::StatelessValidator {
@@ -34,19 +44,16 @@ async fn main() -> Result<()> {
             hints,
             gen_hints,
             hints_out,
+            hints_cache,
+            stream_hints,
+            check_determinism,
             client,
-            include,
-            exclude,
+            filter,
+            expect_hash,
         } => {
             info!(" Client: {:?}", client);
 
-            // To add a client, see docs/adding-a-client.md.
-            let elf = match client {
-                Client::Reth => ELF_RETH,
-                Client::Ethrex => ELF_ETHREX,
-                #[cfg(feature = "ziskethone")]
-                Client::ZiskEthOne => ELF_ZISKETHONE,
-            };
+            let elf = guest_program(*client);
 
             info!(" ELF Name: {}", elf.name());
             if let Some(input_folder) = input_folder {
@@ -58,15 +65,21 @@ async fn main() -> Result<()> {
             if *gen_hints {
                 info!(" Generating hints before running");
             }
-            if let Some(include) = include {
-                info!(" Include Patterns: {:?}", include);
+            if let Some(hints_cache) = hints_cache {
+                info!(" Hints Cache: {}", hints_cache.display());
+            }
+            if *stream_hints {
+                info!(" Streaming hints into each run");
+            }
+            if *check_determinism {
+                info!(" Checking hints determinism");
             }
-            if let Some(exclude) = exclude {
-                info!(" Exclude Patterns: {:?}", exclude);
+            let expect_hash = expect_hash.as_deref().map(parse_hash).transpose()?;
+            if let Some(hash) = &expect_hash {
+                info!(" Expected Block Hash: {}", format_hash(hash));
             }
 
             let runner = BenchmarkRunner::new(
-                elf,
                 cli.action.clone(),
                 cli.output_folder.clone(),
                 cli.force_rerun,
@@ -77,15 +90,111 @@ async fn main() -> Result<()> {
                 hints.clone(),
                 *gen_hints,
                 hints_out.clone(),
+                hints_cache.clone(),
+                *stream_hints,
+                *check_determinism,
                 *client,
+                cli.jobs,
+                Limits {
+                    steps_threshold: cli.steps_threshold,
+                    timeout: cli.timeout.map(Duration::from_secs),
+                },
+                expect_hash,
             )?;
-            runner
-                .run(
-                    input_folder.as_deref(),
-                    include.as_deref(),
-                    exclude.as_deref(),
-                )
-                .await?;
+            runner.run(input_folder.as_deref(), filter).await?;
+        }
+        GuestProgramCommand::Verify {
+            proof,
+            client,
+            expected_hash,
+            input,
+        } => {
+            info!(" Client: {:?}", client);
+            let elf = guest_program(*client);
+            info!(" ELF Name: {}", elf.name());
+            info!(" Proof: {}", proof.display());
+
+            let native_client = create_client(*client);
+            let expected = verify::expected_block_hash(
+                native_client.as_ref(),
+                expected_hash.as_deref(),
+                input.as_deref(),
+            )?;
+
+            let zisk_client = ZiskClient::for_proving(
+                elf,
+                cli.proving_key.clone(),
+                cli.emulator,
+                cli.unlock_mapped_memory,
+                cli.gpu,
+                false,
+                false,
+            )?;
+            zisk_client.setup().await?;
+            verify::verify_proof(&zisk_client, native_client.as_ref(), proof, expected)?;
+        }
+        GuestProgramCommand::Compare {
+            baseline,
+            candidate,
+            threshold,
+            time_threshold,
+            budget,
+            json,
+            allow_incompatible,
+        } => {
+            info!(" Baseline: {}", baseline.display());
+            info!(" Candidate: {}", candidate.display());
+
+            let options = CompareOptions {
+                threshold: *threshold,
+                time_threshold: *time_threshold,
+                budget: *budget,
+                allow_incompatible: *allow_incompatible,
+            };
+            let comparison = compare::compare(baseline, candidate, &options)?;
+            compare::log_table(&comparison);
+            if let Some(json) = json {
+                std::fs::write(json, serde_json::to_string_pretty(&comparison)?)?;
+                info!("Comparison written to {}", json.display());
+            }
+
+            if !comparison.passed() {
+                anyhow::bail!(
+                    "{} input(s) regressed, over the budget of {}",
+                    comparison.regressed.len(),
+                    budget
+                );
+            }
+        }
+        GuestProgramCommand::Differential {
+            input,
+            clients,
+            inputs_dir,
+            rpc_url,
+            json,
+        } => {
+            info!(" Input: {}", input.display());
+
+            let options = DifferentialOptions {
+                clients: clients.clone(),
+                inputs_dirs: inputs_dir.clone(),
+                rpc: rpc_url.as_ref().map(RpcConfig::new),
+                emulator: cli.emulator,
+                unlock_mapped_memory: cli.unlock_mapped_memory,
+            };
+            let report = differential::run_differential(input, &options).await?;
+            differential::log_report(&report);
+            if let Some(json) = json {
+                std::fs::write(json, serde_json::to_string_pretty(&report)?)?;
+                info!("Report written to {}", json.display());
+            }
+
+            if report.diverged() {
+                anyhow::bail!("consensus divergence on block {}", report.block_number);
+            }
+            if report.agreed_hash().is_none() {
+                anyhow::bail!("no client executed block {}", report.block_number);
+            }
         }
     }
 
@@ -116,6 +225,12 @@ fn write_metadata(cli: &Cli) -> Result<()> {
     writeln!(file, "=========================")?;
     writeln!(file, "Action: {:?}", cli.action)?;
     writeln!(file, "Guest Program: {}", cli.guest_program.display_name())?;
+    if let Some(steps) = cli.steps_threshold {
+        writeln!(file, "Steps Threshold: {}", steps)?;
+    }
+    if let Some(secs) = cli.timeout {
+        writeln!(file, "Timeout: {}s", secs)?;
+    }
 
     // Add per-guest metadata
     match &cli.guest_program {
@@ -124,9 +239,12 @@ fn write_metadata(cli: &Cli) -> Result<()> {
             hints,
             gen_hints,
             hints_out,
+            hints_cache,
+            stream_hints,
+            check_determinism,
             client,
-            include,
-            exclude,
+            filter,
+            expect_hash,
         } => {
             writeln!(file, "Client: {:?}", client)?;
             if let Some(input_folder) = input_folder {
@@ -141,11 +259,97 @@ fn write_metadata(cli: &Cli) -> Result<()> {
             if let Some(hints_out) = hints_out {
                 writeln!(file, "Hints Out: {}", hints_out.display())?;
             }
-            if let Some(include) = include {
-                writeln!(file, "Include Patterns: {:?}", include)?;
+            if let Some(hints_cache) = hints_cache {
+                writeln!(file, "Hints Cache: {}", hints_cache.display())?;
+            }
+            if *stream_hints {
+                writeln!(file, "Stream Hints: true")?;
+            }
+            if *check_determinism {
+                writeln!(file, "Check Determinism: true")?;
+            }
+            if !filter.include.is_empty() {
+                writeln!(file, "Include Patterns: {:?}", filter.include)?;
+            }
+            if !filter.exclude.is_empty() {
+                writeln!(file, "Exclude Patterns: {:?}", filter.exclude)?;
+            }
+            if let Some(min_gas) = filter.min_gas {
+                writeln!(file, "Minimum Gas: {} Mgas", min_gas)?;
+            }
+            if let Some(blocks) = &filter.blocks {
+                writeln!(file, "Blocks: {}..={}", blocks.start(), blocks.end())?;
+            }
+            if let Some(chain) = &filter.chain {
+                writeln!(file, "Chain: {}", chain)?;
+            }
+            if let Some(expect_hash) = expect_hash {
+                writeln!(file, "Expected Hash: {}", expect_hash)?;
+            }
+
+            // The structured counterpart, read back by `compare`.
+            RunMetadata::collect(
+                cli.action.clone(),
+                *client,
+                cli.emulator,
+                cli.unlock_mapped_memory,
+                cli.gpu,
+                cli.proving_key.as_deref(),
+            )
+            .write(output_folder)?;
+        }
+        GuestProgramCommand::Verify {
+            proof,
+            client,
+            expected_hash,
+            input,
+        } => {
+            writeln!(file, "Client: {:?}", client)?;
+            writeln!(file, "Proof: {}", proof.display())?;
+            if let Some(expected_hash) = expected_hash {
+                writeln!(file, "Expected Hash: {}", expected_hash)?;
+            }
+            if let Some(input) = input {
+                writeln!(file, "Input: {}", input.display())?;
+            }
+        }
+        GuestProgramCommand::Compare {
+            baseline,
+            candidate,
+            threshold,
+            time_threshold,
+            budget,
+            json: _,
+            allow_incompatible,
+        } => {
+            writeln!(file, "Baseline: {}", baseline.display())?;
+            writeln!(file, "Candidate: {}", candidate.display())?;
+            writeln!(file, "Threshold: {}%", threshold)?;
+            if let Some(time_threshold) = time_threshold {
+                writeln!(file, "Time Threshold: {}%", time_threshold)?;
+            }
+            writeln!(file, "Budget: {}", budget)?;
+            if *allow_incompatible {
+                writeln!(file, "Allow Incompatible: true")?;
+            }
+        }
+        GuestProgramCommand::Differential {
+            input,
+            clients,
+            inputs_dir,
+            rpc_url,
+            json: _,
+        } => {
+            writeln!(file, "Input: {}", input.display())?;
+            if !clients.is_empty() {
+                writeln!(file, "Clients: {:?}", clients)?;
+            }
+            for dir in inputs_dir {
+                writeln!(file, "Inputs Dir: {}", dir.display())?;
             }
-            if let Some(exclude) = exclude {
-                writeln!(file, "Exclude Patterns: {:?}", exclude)?;
+            // The RPC URL is not recorded: it often embeds an API key.
+            if rpc_url.is_some() {
+                writeln!(file, "Generate Missing Inputs: true")?;
             }
         }
     }
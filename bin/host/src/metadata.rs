@@ -0,0 +1,332 @@
+//! `metadata.json`: what a run's numbers depend on besides its inputs — the
+//! guest, the toolchain, the backend and the machine — written next to the
+//! results so `compare` can refuse to diff runs that aren't comparable.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+use tiny_keccak::{Hasher, Keccak};
+use tracing::warn;
+use walkdir::WalkDir;
+
+use input::Client;
+
+use crate::{cli::Action, elfs::guest_program, hints_cache::keccak_hex, system};
+
+pub const FILE_NAME: &str = "metadata.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub action: Action,
+    pub client: String,
+    pub guest: GuestMetadata,
+    /// Version of this host crate.
+    pub host_version: String,
+    /// Commit the host was built from, when built from a git checkout.
+    pub git_commit: Option<String>,
+    /// zisk-sdk version from the workspace lockfile.
+    pub zisk_sdk_version: Option<String>,
+    pub backend: BackendMetadata,
+    /// The proving key used by `verify-constraints` and `prove`.
+    pub proving_key: Option<ProvingKeyMetadata>,
+    pub system: SystemMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestMetadata {
+    pub name: String,
+    /// keccak-256 of the guest ELF, in hex.
+    pub elf_hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendMetadata {
+    /// `assembly` or `emulator`.
+    pub executor: String,
+    pub unlock_mapped_memory: bool,
+    pub gpu: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvingKeyMetadata {
+    pub path: PathBuf,
+    /// See [`proving_key_hash`]; `None` when the key couldn't be read.
+    pub hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemMetadata {
+    pub cpu_model: Option<String>,
+    pub cores: Option<usize>,
+    /// Total memory, in bytes.
+    pub memory: Option<u64>,
+}
+
+impl RunMetadata {
+    /// Describe a run of `client`'s guest. `proving_key` is `--proving-key`;
+    /// when unset, the installed key is recorded for the actions that use one.
+    pub fn collect(
+        action: Action,
+        client: Client,
+        emulator: bool,
+        unlock_mapped_memory: bool,
+        gpu: bool,
+        proving_key: Option<&Path>,
+    ) -> Self {
+        let elf = guest_program(client);
+        let proving_key = match action {
+            Action::Execute => None,
+            Action::VerifyConstraints | Action::Prove => proving_key
+                .map(Path::to_path_buf)
+                .or_else(installed_proving_key),
+        };
+        Self {
+            client: input::create_client(client).name().to_string(),
+            guest: GuestMetadata {
+                name: elf.name().to_string(),
+                elf_hash: keccak_hex(elf.elf()),
+            },
+            action,
+            host_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: non_empty(env!("ZEC_GIT_COMMIT")),
+            zisk_sdk_version: non_empty(env!("ZEC_ZISK_SDK_VERSION")),
+            backend: BackendMetadata {
+                executor: if emulator { "emulator" } else { "assembly" }.to_string(),
+                unlock_mapped_memory,
+                gpu,
+            },
+            proving_key: proving_key.map(|path| ProvingKeyMetadata {
+                hash: proving_key_hash(&path)
+                    .inspect_err(|e| warn!("Could not hash the proving key: {e:#}"))
+                    .ok(),
+                path,
+            }),
+            system: SystemMetadata {
+                cpu_model: system::cpu_model(),
+                cores: std::thread::available_parallelism().ok().map(|n| n.get()),
+                memory: system::total_memory_bytes(),
+            },
+        }
+    }
+
+    pub fn write(&self, output_folder: &Path) -> Result<()> {
+        let path = output_folder.join(FILE_NAME);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The metadata of the run in `output_folder`; `None` for runs written
+    /// before it was recorded.
+    pub fn load(output_folder: &Path) -> Result<Option<Self>> {
+        let path = output_folder.join(FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Why this run's numbers can't be compared against `other`'s. The guest,
+    /// host and ZisK builds may differ — that is what a comparison is for — but
+    /// the action and the client must match. With `timed`, time is compared
+    /// too, so the backend and the machine must match as well.
+    pub fn incompatibilities(&self, other: &Self, timed: bool) -> Vec<String> {
+        let mut reasons = Vec::new();
+        let mut check = |what: &str, a: String, b: String| {
+            if a != b {
+                reasons.push(format!("{what}: {a} vs {b}"));
+            }
+        };
+        check(
+            "action",
+            format!("{:?}", self.action),
+            format!("{:?}", other.action),
+        );
+        check("client", self.client.clone(), other.client.clone());
+        if timed {
+            check(
+                "backend",
+                format!("{:?}", self.backend),
+                format!("{:?}", other.backend),
+            );
+            check(
+                "CPU",
+                display(&self.system.cpu_model),
+                display(&other.system.cpu_model),
+            );
+            check(
+                "cores",
+                display(&self.system.cores),
+                display(&other.system.cores),
+            );
+        }
+        reasons
+    }
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+fn display<T: std::fmt::Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(T::to_string)
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Where `cargo-zisk` installs the proving key.
+fn installed_proving_key() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".zisk/provingKey"))
+}
+
+/// keccak-256 of a proving key, in hex: of its contents when it is a file, of
+/// its file names and sizes when it is a directory (the installed key is tens
+/// of GB, too much to read on every run).
+fn proving_key_hash(path: &Path) -> Result<String> {
+    let mut keccak = Keccak::v256();
+    if path.is_dir() {
+        for entry in WalkDir::new(path).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let relative = entry.path().strip_prefix(path)?;
+                keccak.update(relative.to_string_lossy().as_bytes());
+                keccak.update(&entry.metadata()?.len().to_le_bytes());
+            }
+        }
+    } else {
+        let mut file =
+            fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut buf = vec![0u8; 1 << 20];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            keccak.update(&buf[..n]);
+        }
+    }
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    Ok(hash.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> RunMetadata {
+        RunMetadata {
+            action: Action::Execute,
+            client: "reth".to_string(),
+            guest: GuestMetadata {
+                name: "stateless-validator-reth".to_string(),
+                elf_hash: "00".to_string(),
+            },
+            host_version: "0.1.0".to_string(),
+            git_commit: None,
+            zisk_sdk_version: None,
+            backend: BackendMetadata {
+                executor: "assembly".to_string(),
+                unlock_mapped_memory: false,
+                gpu: false,
+            },
+            proving_key: None,
+            system: SystemMetadata {
+                cpu_model: Some("AMD EPYC 9654".to_string()),
+                cores: Some(96),
+                memory: Some(1 << 40),
+            },
+        }
+    }
+
+    #[test]
+    fn builds_may_differ() {
+        let mut other = metadata();
+        other.guest.elf_hash = "11".to_string();
+        other.host_version = "0.2.0".to_string();
+        other.git_commit = Some("abc".to_string());
+        other.zisk_sdk_version = Some("0.16.0".to_string());
+        other.system.memory = Some(1 << 30);
+        assert!(metadata().incompatibilities(&other, true).is_empty());
+    }
+
+    #[test]
+    fn action_and_client_must_match() {
+        let mut other = metadata();
+        other.action = Action::Prove;
+        other.client = "ethrex".to_string();
+        assert_eq!(
+            metadata().incompatibilities(&other, false),
+            ["action: Execute vs Prove", "client: reth vs ethrex"]
+        );
+    }
+
+    #[test]
+    fn backend_and_machine_must_match_only_when_timed() {
+        let mut other = metadata();
+        other.backend.executor = "emulator".to_string();
+        other.system.cpu_model = None;
+        other.system.cores = Some(64);
+        assert!(metadata().incompatibilities(&other, false).is_empty());
+
+        let reasons = metadata().incompatibilities(&other, true);
+        assert_eq!(reasons.len(), 3, "{reasons:?}");
+        assert!(reasons[0].starts_with("backend: "), "{reasons:?}");
+        assert_eq!(reasons[1], "CPU: AMD EPYC 9654 vs unknown");
+        assert_eq!(reasons[2], "cores: 96 vs 64");
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("metadata-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn file_key_hashes_its_contents() {
+        let dir = temp_dir("key-file");
+        let a = dir.join("a.key");
+        let b = dir.join("b.key");
+        fs::write(&a, b"proving key").unwrap();
+        fs::write(&b, b"proving key").unwrap();
+
+        let hash = proving_key_hash(&a).unwrap();
+        assert_eq!(hash, keccak_hex(b"proving key"));
+        assert_eq!(hash, proving_key_hash(&b).unwrap());
+
+        fs::write(&b, b"another key").unwrap();
+        assert_ne!(hash, proving_key_hash(&b).unwrap());
+        assert!(proving_key_hash(&dir.join("missing.key")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directory_key_hashes_names_and_sizes() {
+        let dir = temp_dir("key-dir");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.bin"), b"1234").unwrap();
+        fs::write(dir.join("sub/b.bin"), b"56").unwrap();
+        let hash = proving_key_hash(&dir).unwrap();
+
+        // Same names and sizes, other contents: not read, so the same hash.
+        fs::write(dir.join("a.bin"), b"abcd").unwrap();
+        assert_eq!(proving_key_hash(&dir).unwrap(), hash);
+
+        fs::write(dir.join("a.bin"), b"abcde").unwrap();
+        let resized = proving_key_hash(&dir).unwrap();
+        assert_ne!(resized, hash);
+
+        fs::rename(dir.join("sub/b.bin"), dir.join("sub/c.bin")).unwrap();
+        assert_ne!(proving_key_hash(&dir).unwrap(), resized);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,430 @@
+//! Run-level benchmark report: `summary.json` and `summary.csv` in the output
+//! folder, aggregating the per-input results of one run (including inputs
+//! skipped because an earlier run already produced their result).
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path};
+use tracing::{error, info, warn};
+
+use crate::failure::{Failure, FailureKind};
+
+/// One input's numbers, as they appear in the summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct InputSummary {
+    pub test_name: String,
+    pub chain: Option<String>,
+    pub block_number: Option<u64>,
+    pub time: f64,
+    pub steps: u64,
+    pub cost: Option<u64>,
+    pub gas_used: Option<u64>,
+    pub tx_count: Option<u64>,
+}
+
+impl InputSummary {
+    fn steps_per_gas(&self) -> Option<f64> {
+        self.gas_used
+            .filter(|gas| *gas > 0)
+            .map(|gas| self.steps as f64 / gas as f64)
+    }
+
+    fn cost_per_gas(&self) -> Option<f64> {
+        match (self.cost, self.gas_used) {
+            (Some(cost), Some(gas)) if gas > 0 => Some(cost as f64 / gas as f64),
+            _ => None,
+        }
+    }
+}
+
+/// An input that failed, with its classified failure.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedInput {
+    pub test_name: String,
+    #[serde(flatten)]
+    pub failure: Failure,
+}
+
+/// Outcomes of a run, collected as inputs finish.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    inputs: Vec<InputSummary>,
+    failed: Vec<FailedInput>,
+    /// Inputs over `--steps-threshold` or `--timeout`, kept apart from `failed`.
+    limit_exceeded: Vec<FailedInput>,
+    skipped: Vec<String>,
+    passed: usize,
+}
+
+impl RunReport {
+    pub fn record_passed(&mut self, summary: InputSummary) {
+        self.passed += 1;
+        self.inputs.push(summary);
+    }
+
+    pub fn record_failed(&mut self, test_name: &str, failure: Failure) {
+        let input = FailedInput {
+            test_name: test_name.to_string(),
+            failure,
+        };
+        if matches!(input.failure.kind, FailureKind::LimitExceeded { .. }) {
+            warn!(
+                "Limit exceeded for {}: {}",
+                test_name, input.failure.message
+            );
+            self.limit_exceeded.push(input);
+        } else {
+            error!(
+                "Failed to run benchmark for {} [{}]: {}",
+                test_name, input.failure.kind, input.failure.message
+            );
+            self.failed.push(input);
+        }
+    }
+
+    /// `previous` is the passing result an earlier run left behind; it keeps
+    /// the input's numbers in the summary.
+    pub fn record_skipped(&mut self, test_name: &str, previous: InputSummary) {
+        self.skipped.push(test_name.to_string());
+        self.inputs.push(previous);
+    }
+
+    pub fn passed(&self) -> usize {
+        self.passed
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed.len()
+    }
+
+    pub fn limit_exceeded(&self) -> usize {
+        self.limit_exceeded.len()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped.len()
+    }
+
+    /// Write `summary.json` and `summary.csv` into `output_folder`.
+    pub fn write(mut self, output_folder: &Path) -> Result<()> {
+        self.inputs.sort_by(|a, b| a.test_name.cmp(&b.test_name));
+        self.failed.sort_by(|a, b| a.test_name.cmp(&b.test_name));
+        self.limit_exceeded
+            .sort_by(|a, b| a.test_name.cmp(&b.test_name));
+        self.skipped.sort();
+
+        fs::create_dir_all(output_folder)?;
+        let summary = self.summary();
+        let json_path = output_folder.join("summary.json");
+        fs::write(&json_path, serde_json::to_string_pretty(&summary)?)
+            .with_context(|| format!("Failed to write {}", json_path.display()))?;
+        let csv_path = output_folder.join("summary.csv");
+        fs::write(&csv_path, self.csv())
+            .with_context(|| format!("Failed to write {}", csv_path.display()))?;
+
+        info!(
+            "Summary written to {} and {}",
+            json_path.display(),
+            csv_path.display()
+        );
+        for (category, count) in &summary.failures_by_category {
+            info!("Failures ({}): {}", category, count);
+        }
+        if let Some(fit) = &summary.regressions.steps_per_gas {
+            info!(
+                "Steps per gas: {:.2} (r² {:.3}, {} inputs)",
+                fit.slope, fit.r_squared, fit.n_points
+            );
+        }
+        if let Some(fit) = &summary.regressions.cost_per_gas {
+            info!(
+                "Cost per gas: {:.2} (r² {:.3}, {} inputs)",
+                fit.slope, fit.r_squared, fit.n_points
+            );
+        }
+        Ok(())
+    }
+
+    fn summary(&self) -> Summary<'_> {
+        let times: Vec<f64> = self.inputs.iter().map(|i| i.time).collect();
+        let steps: Vec<f64> = self.inputs.iter().map(|i| i.steps as f64).collect();
+        let costs: Vec<f64> = self
+            .inputs
+            .iter()
+            .filter_map(|i| i.cost.map(|c| c as f64))
+            .collect();
+
+        let fit = |metric: fn(&InputSummary) -> Option<u64>| {
+            let points: Vec<(f64, f64)> = self
+                .inputs
+                .iter()
+                .filter_map(|i| Some((i.gas_used? as f64, metric(i)? as f64)))
+                .collect();
+            Regression::fit(&points)
+        };
+
+        Summary {
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| InputRow {
+                    input,
+                    steps_per_gas: input.steps_per_gas(),
+                    cost_per_gas: input.cost_per_gas(),
+                })
+                .collect(),
+            totals: Totals {
+                inputs: self.inputs.len(),
+                passed: self.passed,
+                failed: self.failed.len(),
+                limit_exceeded: self.limit_exceeded.len(),
+                skipped: self.skipped.len(),
+                time: times.iter().sum(),
+                steps: self.inputs.iter().map(|i| i.steps).sum(),
+                cost: self.inputs.iter().filter_map(|i| i.cost).sum(),
+                gas_used: self.inputs.iter().filter_map(|i| i.gas_used).sum(),
+                tx_count: self.inputs.iter().filter_map(|i| i.tx_count).sum(),
+            },
+            percentiles: PercentileSet {
+                time: Percentiles::of(times),
+                steps: Percentiles::of(steps),
+                cost: Percentiles::of(costs),
+            },
+            regressions: Regressions {
+                steps_per_gas: fit(|i| Some(i.steps)),
+                cost_per_gas: fit(|i| i.cost),
+            },
+            failures_by_category: self.failed.iter().fold(BTreeMap::new(), |mut acc, f| {
+                *acc.entry(f.failure.kind.category()).or_default() += 1;
+                acc
+            }),
+            failed: &self.failed,
+            limit_exceeded: &self.limit_exceeded,
+            skipped: &self.skipped,
+        }
+    }
+
+    fn csv(&self) -> String {
+        let mut out = String::from(
+            "test_name,chain,block_number,time,steps,cost,gas_used,tx_count,steps_per_gas,cost_per_gas\n",
+        );
+        let opt = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_default();
+        let ratio = |v: Option<f64>| v.map(|v| format!("{v:.4}")).unwrap_or_default();
+        for input in &self.inputs {
+            let _ = writeln!(
+                out,
+                "{},{},{},{:.3},{},{},{},{},{},{}",
+                csv_field(&input.test_name),
+                input.chain.as_deref().map(csv_field).unwrap_or_default(),
+                opt(input.block_number),
+                input.time,
+                input.steps,
+                opt(input.cost),
+                opt(input.gas_used),
+                opt(input.tx_count),
+                ratio(input.steps_per_gas()),
+                ratio(input.cost_per_gas()),
+            );
+        }
+        out
+    }
+}
+
+#[derive(Serialize)]
+struct Summary<'a> {
+    totals: Totals,
+    percentiles: PercentileSet,
+    regressions: Regressions,
+    failures_by_category: BTreeMap<&'static str, usize>,
+    failed: &'a [FailedInput],
+    limit_exceeded: &'a [FailedInput],
+    skipped: &'a [String],
+    inputs: Vec<InputRow<'a>>,
+}
+
+#[derive(Serialize)]
+struct InputRow<'a> {
+    #[serde(flatten)]
+    input: &'a InputSummary,
+    steps_per_gas: Option<f64>,
+    cost_per_gas: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Totals {
+    inputs: usize,
+    passed: usize,
+    failed: usize,
+    limit_exceeded: usize,
+    skipped: usize,
+    time: f64,
+    steps: u64,
+    cost: u64,
+    gas_used: u64,
+    tx_count: u64,
+}
+
+#[derive(Serialize)]
+struct PercentileSet {
+    time: Option<Percentiles>,
+    steps: Option<Percentiles>,
+    cost: Option<Percentiles>,
+}
+
+#[derive(Serialize)]
+struct Percentiles {
+    min: f64,
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    max: f64,
+    mean: f64,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles; `None` when there are no values.
+    fn of(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let rank = |p: f64| {
+            let idx = ((p / 100.0) * values.len() as f64).ceil() as usize;
+            values[idx.clamp(1, values.len()) - 1]
+        };
+        Some(Self {
+            min: values[0],
+            p50: rank(50.0),
+            p90: rank(90.0),
+            p99: rank(99.0),
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Regressions {
+    steps_per_gas: Option<Regression>,
+    cost_per_gas: Option<Regression>,
+}
+
+/// Least-squares fit of a metric against gas used: `metric ≈ slope * gas + intercept`.
+#[derive(Serialize)]
+struct Regression {
+    slope: f64,
+    intercept: f64,
+    r_squared: f64,
+    n_points: usize,
+}
+
+impl Regression {
+    /// `None` with fewer than two points or when all points share one gas value.
+    fn fit(points: &[(f64, f64)]) -> Option<Self> {
+        let n = points.len() as f64;
+        if points.len() < 2 {
+            return None;
+        }
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if sxx == 0.0 {
+            return None;
+        }
+        let sxy: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let syy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+        let slope = sxy / sxx;
+        let r_squared = if syy == 0.0 {
+            1.0
+        } else {
+            (sxy * sxy) / (sxx * syy)
+        };
+        Some(Self {
+            slope,
+            intercept: mean_y - slope * mean_x,
+            r_squared,
+            n_points: points.len(),
+        })
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_of_no_values() {
+        assert!(Percentiles::of(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn percentiles_of_one_value() {
+        let p = Percentiles::of(vec![7.0]).unwrap();
+        for value in [p.min, p.p50, p.p90, p.p99, p.max, p.mean] {
+            assert_eq!(value, 7.0);
+        }
+    }
+
+    #[test]
+    fn percentiles_are_nearest_rank() {
+        // 1..=100 in reverse: sorted first, then rank p is the p-th value.
+        let p = Percentiles::of((1..=100).rev().map(f64::from).collect()).unwrap();
+        assert_eq!(
+            (p.min, p.p50, p.p90, p.p99, p.max),
+            (1.0, 50.0, 90.0, 99.0, 100.0)
+        );
+        assert_eq!(p.mean, 50.5);
+
+        let p = Percentiles::of(vec![3.0, 1.0, 2.0]).unwrap();
+        assert_eq!((p.p50, p.p90, p.p99), (2.0, 3.0, 3.0));
+    }
+
+    #[test]
+    fn fit_needs_two_points() {
+        assert!(Regression::fit(&[]).is_none());
+        assert!(Regression::fit(&[(1.0, 2.0)]).is_none());
+    }
+
+    #[test]
+    fn fit_needs_distinct_gas() {
+        assert!(Regression::fit(&[(5.0, 1.0), (5.0, 2.0), (5.0, 3.0)]).is_none());
+    }
+
+    #[test]
+    fn fit_recovers_a_line() {
+        let points: Vec<(f64, f64)> = (0..10).map(|x| (x as f64, 2.0 * x as f64 + 1.0)).collect();
+        let fit = Regression::fit(&points).unwrap();
+        assert!((fit.slope - 2.0).abs() < 1e-12);
+        assert!((fit.intercept - 1.0).abs() < 1e-12);
+        assert!((fit.r_squared - 1.0).abs() < 1e-12);
+        assert_eq!(fit.n_points, 10);
+    }
+
+    #[test]
+    fn fit_of_noisy_points() {
+        // Sxx = 5, Sxy = 3, Syy = 5.
+        let fit = Regression::fit(&[(0.0, 1.0), (1.0, 0.0), (2.0, 3.0), (3.0, 2.0)]).unwrap();
+        assert!((fit.slope - 0.6).abs() < 1e-12);
+        assert!((fit.intercept - 0.6).abs() < 1e-12);
+        assert!((fit.r_squared - 0.36).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fit_with_constant_metric() {
+        let fit = Regression::fit(&[(1.0, 4.0), (2.0, 4.0), (3.0, 4.0)]).unwrap();
+        assert_eq!(fit.slope, 0.0);
+        assert_eq!(fit.intercept, 4.0);
+        assert_eq!(fit.r_squared, 1.0);
+    }
+}
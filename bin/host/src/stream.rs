@@ -0,0 +1,99 @@
+//! `--stream-hints`: generate an input's hints natively while the ZisK run
+//! consumes them over a Unix socket, so no hints file touches disk and the
+//! measured time is the end-to-end latency a real-time prover would see.
+
+use anyhow::{Context, Result};
+use std::{
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+use tracing::info;
+use zisk_sdk::ZiskStdin;
+
+use input::{Client, ExecutionClient, create_client, generate_hints_to_socket};
+
+/// Socket the hints are streamed through. One per process: hints generation is
+/// process-global, so only one input streams at a time.
+pub fn socket_path() -> PathBuf {
+    std::env::temp_dir().join(format!("zec-hints-{}.sock", std::process::id()))
+}
+
+/// Hints generation running on a blocking thread, serving a socket.
+pub struct HintStream {
+    socket: PathBuf,
+    generator: Option<JoinHandle<Result<(Duration, Duration)>>>,
+    started: Instant,
+}
+
+/// Timings of a streamed run.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamTimings {
+    /// From the start of hints generation to the end of the run.
+    pub end_to_end: Duration,
+    /// Native execution time of the hints generation.
+    pub generation: Duration,
+}
+
+impl HintStream {
+    /// Start generating `input`'s hints into `socket`, returning once it is
+    /// listening so the run can connect.
+    pub async fn start(client: Client, input: &Path, socket: PathBuf) -> Result<Self> {
+        let stdin = ZiskStdin::from_file(input).context("Failed to load input file")?;
+        // A socket left behind by an interrupted run would make binding fail.
+        let _ = std::fs::remove_file(&socket);
+
+        let started = Instant::now();
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let generator = {
+            let socket = socket.clone();
+            tokio::task::spawn_blocking(move || {
+                let client: Arc<dyn ExecutionClient> = create_client(client).into();
+                generate_hints_to_socket(&stdin, socket, None, None, Some(ready_tx), None, &client)
+            })
+        };
+
+        if ready_rx.await.is_err() {
+            // The generator ended before listening; its error says why.
+            generator.await.context("Hints generator panicked")??;
+            anyhow::bail!("hints generation ended before its socket was ready");
+        }
+        Ok(Self {
+            socket,
+            generator: Some(generator),
+            started,
+        })
+    }
+
+    /// Call once the run consuming the socket has finished: waits for the
+    /// generator and reports its errors.
+    pub async fn finish(mut self) -> Result<StreamTimings> {
+        let end_to_end = self.started.elapsed();
+        let generator = self.generator.take().expect("finish is called once");
+        let (generation, _) = generator
+            .await
+            .context("Hints generator panicked")?
+            .context("Streaming hints generation failed")?;
+        info!(
+            "Streamed hints — end to end: {:.2?}, generation: {:.2?}",
+            end_to_end, generation
+        );
+        Ok(StreamTimings {
+            end_to_end,
+            generation,
+        })
+    }
+}
+
+impl Drop for HintStream {
+    fn drop(&mut self) {
+        // A run that failed before connecting leaves the generator blocked in
+        // accept; connecting and hanging up lets it fail and end.
+        if self.generator.is_some() {
+            let _ = UnixStream::connect(&self.socket);
+        }
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
@@ -0,0 +1,41 @@
+//! Host process resource usage and the machine it runs on, read from `/proc`.
+//! Linux only: elsewhere the readings are `None` and resets are no-ops.
+
+use std::fs;
+
+/// Reset the process' peak resident set size, so the next [`peak_rss_bytes`]
+/// covers only what runs after this call. Best-effort: writing `5` to
+/// `clear_refs` needs Linux >= 4.0.
+pub fn reset_peak_rss() {
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+/// Peak resident set size (`VmHWM`) of this process, in bytes.
+pub fn peak_rss_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// CPU model name, as `/proc/cpuinfo` reports it.
+pub fn cpu_model() -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("model name"))
+        .and_then(|v| v.split_once(':'))
+        .map(|(_, model)| model.trim().to_string())
+}
+
+/// Total physical memory (`MemTotal`), in bytes.
+pub fn total_memory_bytes() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
@@ -0,0 +1,59 @@
+//! Offline proof verification: check a proof written by `-a prove` against the
+//! guest program's verification key, then cross-check the block hash it commits.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use tracing::info;
+
+use input::ExecutionClient;
+
+use crate::zisk::{ZiskClient, format_hash, parse_hash};
+
+/// Resolve the block hash a proof is expected to commit: given explicitly, or
+/// read from the block header carried by an input `.bin`.
+pub fn expected_block_hash(
+    client: &dyn ExecutionClient,
+    expected_hash: Option<&str>,
+    input: Option<&Path>,
+) -> Result<Option<[u8; 32]>> {
+    if let Some(hash) = expected_hash {
+        return parse_hash(hash).map(Some);
+    }
+    let Some(input) = input else {
+        return Ok(None);
+    };
+    let bytes = std::fs::read(input)
+        .with_context(|| format!("Failed to read input file {}", input.display()))?;
+    client
+        .input_block_hash(&bytes)
+        .with_context(|| format!("Failed to read the block hash from {}", input.display()))
+        .map(Some)
+}
+
+/// Verify `proof` and return the block hash it commits. Fails if the proof is
+/// invalid, or if it commits a different hash than `expected`.
+pub fn verify_proof(
+    zisk_client: &ZiskClient,
+    client: &dyn ExecutionClient,
+    proof: &Path,
+    expected: Option<[u8; 32]>,
+) -> Result<[u8; 32]> {
+    let publics = zisk_client.verify(proof)?;
+    info!("Proof is valid: {}", proof.display());
+
+    let committed = client.committed_block_hash(&publics)?;
+    info!("Committed block hash: {}", format_hash(&committed));
+
+    if let Some(expected) = expected {
+        if committed != expected {
+            anyhow::bail!(
+                "proof commits block hash {}, expected {}",
+                format_hash(&committed),
+                format_hash(&expected)
+            );
+        }
+        info!("Committed block hash matches the expected one");
+    }
+
+    Ok(committed)
+}
@@ -6,10 +6,14 @@ use std::{
 };
 use tracing::info;
 
+use input::InputMetadata;
+
+use crate::cli::Action;
+
 use zisk_sdk::{
     AsmOptions, EmbeddedClient, EmbeddedClientBuilder, EmbeddedExecuteOnlyClient, ExecuteOutput,
     ExecutorKind, GuestProgram, ProverClient, VerifyConstraintsExtension, WitnessBuilderExt,
-    ZiskHints, ZiskStdin,
+    ZiskHints, ZiskProof, ZiskStdin,
 };
 
 enum Backend {
@@ -25,8 +29,57 @@ pub struct ZiskClient {
     use_hints: bool,
 }
 
+/// Where a run's hints come from.
+#[derive(Debug, Clone)]
+pub enum HintsSource {
+    /// A pre-generated `.hints` file.
+    File(PathBuf),
+    /// A Unix socket a native run streams the hints into while the ZisK run
+    /// consumes them (see [`crate::stream`]).
+    Socket(PathBuf),
+}
+
+impl HintsSource {
+    fn load(&self) -> Result<ZiskHints> {
+        match self {
+            Self::File(path) => ZiskHints::from_file(path).context("Failed to load hints file"),
+            Self::Socket(path) => {
+                ZiskHints::from_socket(path).context("Failed to connect to the hints socket")
+            }
+        }
+    }
+}
+
+/// A ZisK run that failed inside the SDK, as opposed to while the host was
+/// preparing it. The SDK's errors carry no type to tell why a run failed, so
+/// failure classification only relies on what failed and whether hints drove
+/// it.
+#[derive(Debug)]
+pub struct ExecutionFailed {
+    pub action: Action,
+    /// Whether the run was driven by hints.
+    pub with_hints: bool,
+    pub source: anyhow::Error,
+}
+
+impl std::fmt::Display for ExecutionFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.action {
+            Action::Execute => write!(f, "ZisK execution failed"),
+            Action::VerifyConstraints => write!(f, "ZisK constraint verification failed"),
+            Action::Prove => write!(f, "ZisK proving failed"),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionFailed {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
 /// Output metrics from ZisK execution
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ZiskExecutionMetrics {
     #[serde(skip)]
     pub duration: Duration,
@@ -34,13 +87,41 @@ pub struct ZiskExecutionMetrics {
     pub cost: Option<u64>,
     pub tx_count: Option<u64>,
     pub gas_used: Option<u64>,
+    /// Lowercase chain name, as in input filenames (e.g. `mainnet`).
+    pub chain: Option<String>,
+    pub block_number: Option<u64>,
+    /// Block hash the guest committed (`0x`-prefixed hex), decoded from the
+    /// run's public outputs.
+    pub committed_block_hash: Option<String>,
+}
+
+impl ZiskExecutionMetrics {
+    /// Fill in the block metadata decoded from the input that was run.
+    pub fn set_input_metadata(&mut self, metadata: &InputMetadata) {
+        self.chain = metadata.chain_name.map(str::to_lowercase);
+        self.block_number = Some(metadata.block_number);
+        self.tx_count = Some(metadata.tx_count);
+        self.gas_used = metadata.gas_used;
+    }
+}
+
+/// Output of an execution or proving run: its metrics plus the guest's public
+/// outputs (what it committed via `ziskos::io::commit`).
+#[derive(Debug)]
+pub struct ZiskOutput {
+    pub metrics: ZiskExecutionMetrics,
+    pub publics: Vec<u8>,
 }
 
 impl std::fmt::Display for ZiskExecutionMetrics {
     /// Human-readable, one line: only the fields that are present, with grouped
     /// digits. `duration` is omitted (logged separately as the elapsed time).
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut parts = vec![format!("steps: {}", group_thousands(self.steps))];
+        let mut parts = Vec::new();
+        if let Some(block_number) = self.block_number {
+            parts.push(format!("block: {block_number}"));
+        }
+        parts.push(format!("steps: {}", group_thousands(self.steps)));
         if let Some(cost) = self.cost {
             parts.push(format!("cost: {}", group_thousands(cost)));
         }
@@ -148,66 +229,70 @@ impl ZiskClient {
         Ok(())
     }
 
-    fn validate_sources(&self, input_file: Option<&Path>, hints_file: Option<&Path>) -> Result<()> {
-        if input_file.is_some() && hints_file.is_some() {
-            anyhow::bail!("provide either an input file or a hints file, not both");
+    fn validate_sources(
+        &self,
+        input_file: Option<&Path>,
+        hints: Option<&HintsSource>,
+    ) -> Result<()> {
+        if input_file.is_some() && hints.is_some() {
+            anyhow::bail!("provide either an input file or hints, not both");
         }
-        if input_file.is_none() && hints_file.is_none() {
-            anyhow::bail!("provide an input file or a hints file");
+        if input_file.is_none() && hints.is_none() {
+            anyhow::bail!("provide an input file or hints");
         }
-        if hints_file.is_some() && !self.use_hints {
-            anyhow::bail!("a hints file was provided but the client was not set up with hints");
+        if hints.is_some() && !self.use_hints {
+            anyhow::bail!("hints were provided but the client was not set up with hints");
         }
         Ok(())
     }
 
-    /// Execute the program and return execution metrics.
+    /// Execute the program and return its metrics and public outputs.
     ///
     /// Exactly one source drives the run: `input_file` (normal execution) or
-    /// `hints_file` (run with pre-generated hints — the input is carried by the
-    /// hints, so stdin is empty). Hints require the assembly executor and a setup
-    /// done with hints.
+    /// `hints` (run with pre-generated or streamed hints — the input is carried
+    /// by the hints, so stdin is empty). Hints require the assembly executor and
+    /// a setup done with hints.
     pub async fn execute(
         &self,
         input_file: Option<&Path>,
-        hints_file: Option<&Path>,
-    ) -> Result<ZiskExecutionMetrics> {
-        self.validate_sources(input_file, hints_file)?;
+        hints: Option<&HintsSource>,
+    ) -> Result<ZiskOutput> {
+        self.validate_sources(input_file, hints)?;
 
         let stdin = match input_file {
             Some(file) => ZiskStdin::from_file(file).context("Failed to load input file")?,
             None => ZiskStdin::new(),
         };
 
-        let hints = match hints_file {
-            Some(path) => Some(ZiskHints::from_file(path).context("Failed to load hints file")?),
-            None => None,
-        };
+        let hints = hints.map(HintsSource::load).transpose()?;
+        let with_hints = hints.is_some();
 
-        match &self.backend {
-            Backend::ExecuteOnly(client) => {
-                let result = client.execute(&self.program, stdin, hints)?;
-                log_plan(&result);
-                Ok(execute_metrics(
-                    result.get_execution_time(),
-                    result.get_execution_steps(),
-                    result.get_execution_cost(),
-                ))
-            }
+        let result: Result<ExecuteOutput> = match &self.backend {
+            Backend::ExecuteOnly(client) => client
+                .execute(&self.program, stdin, hints)
+                .map_err(Into::into),
             Backend::Full(client) => {
                 let mut request = client.execute(&self.program, stdin).executor(self.executor);
                 if let Some(hints) = hints {
                     request = request.hints(hints);
                 }
-                let result = request.run()?.await?;
-                log_plan(&result);
-                Ok(execute_metrics(
-                    result.get_execution_time(),
-                    result.get_execution_steps(),
-                    result.get_execution_cost(),
-                ))
+                async { Ok(request.run()?.await?) }.await
             }
-        }
+        };
+        let result = result.map_err(|source| ExecutionFailed {
+            action: Action::Execute,
+            with_hints,
+            source,
+        })?;
+        log_plan(&result);
+        Ok(ZiskOutput {
+            metrics: execute_metrics(
+                result.get_execution_time(),
+                result.get_execution_steps(),
+                result.get_execution_cost(),
+            ),
+            publics: result.get_publics().to_vec(),
+        })
     }
 
     /// Verify constraints for the program.
@@ -215,9 +300,9 @@ impl ZiskClient {
     pub async fn verify_constraints(
         &self,
         input_file: Option<&Path>,
-        hints_file: Option<&Path>,
+        hints: Option<&HintsSource>,
     ) -> Result<ZiskExecutionMetrics> {
-        self.validate_sources(input_file, hints_file)?;
+        self.validate_sources(input_file, hints)?;
 
         let stdin = match input_file {
             Some(file) => ZiskStdin::from_file(file).context("Failed to load input file")?,
@@ -229,12 +314,16 @@ impl ZiskClient {
         };
 
         let mut request = client.verify_constraints(&self.program, stdin);
-        if let Some(path) = hints_file {
-            let hints = ZiskHints::from_file(path).context("Failed to load hints file")?;
-            request = request.hints(hints);
+        if let Some(hints) = hints {
+            request = request.hints(hints.load()?);
         }
 
-        let result = request.run()?.await?;
+        let result: Result<_> = async { Ok(request.run()?.await?) }.await;
+        let result = result.map_err(|source| ExecutionFailed {
+            action: Action::VerifyConstraints,
+            with_hints: hints.is_some(),
+            source,
+        })?;
 
         Ok(ZiskExecutionMetrics {
             duration: Duration::from_millis(result.get_duration()),
@@ -242,12 +331,111 @@ impl ZiskClient {
             cost: Some(result.get_execution_total_cost()),
             tx_count: None,
             gas_used: None,
+            chain: None,
+            block_number: None,
+            committed_block_hash: None,
         })
     }
+
+    /// Generate a proof for the program.
+    ///
+    /// Sources follow [`execute`](Self::execute). When `proof_path` is `Some`,
+    /// the proof is written there.
+    pub async fn prove(
+        &self,
+        input_file: Option<&Path>,
+        hints: Option<&HintsSource>,
+        proof_path: Option<&Path>,
+    ) -> Result<ZiskOutput> {
+        self.validate_sources(input_file, hints)?;
+
+        let stdin = match input_file {
+            Some(file) => ZiskStdin::from_file(file).context("Failed to load input file")?,
+            None => ZiskStdin::new(),
+        };
+
+        let Backend::Full(client) = &self.backend else {
+            anyhow::bail!("prove requires the full client, not execute-only");
+        };
+
+        let mut request = client.prove(&self.program, stdin);
+        if let Some(hints) = hints {
+            request = request.hints(hints.load()?);
+        }
+
+        let result: Result<_> = async { Ok(request.run()?.await?) }.await;
+        let result = result.map_err(|source| ExecutionFailed {
+            action: Action::Prove,
+            with_hints: hints.is_some(),
+            source,
+        })?;
+
+        if let Some(path) = proof_path {
+            result
+                .save_proof(path)
+                .with_context(|| format!("Failed to write proof to {}", path.display()))?;
+        }
+
+        Ok(ZiskOutput {
+            metrics: ZiskExecutionMetrics {
+                duration: Duration::from_millis(result.get_duration()),
+                steps: result.get_execution_steps(),
+                cost: Some(result.get_execution_total_cost()),
+                tx_count: None,
+                gas_used: None,
+                chain: None,
+                block_number: None,
+                committed_block_hash: None,
+            },
+            publics: result.get_publics().to_vec(),
+        })
+    }
+
+    /// Verify a proof written by [`prove`](Self::prove) against the program's
+    /// verification key, returning the proof's public outputs.
+    pub fn verify(&self, proof_path: &Path) -> Result<Vec<u8>> {
+        let Backend::Full(client) = &self.backend else {
+            anyhow::bail!("verify requires the full client, not execute-only");
+        };
+
+        let proof = ZiskProof::load(proof_path)
+            .with_context(|| format!("Failed to load proof from {}", proof_path.display()))?;
+        let vk = client
+            .vk(&self.program)
+            .context("Failed to derive the program verification key")?;
+        client
+            .verify(&proof, &vk)
+            .context("Proof verification failed")?;
+
+        Ok(proof.get_publics().to_vec())
+    }
+}
+
+/// Format a 32-byte hash as `0x`-prefixed lowercase hex.
+pub fn format_hash(hash: &[u8; 32]) -> String {
+    let hex: String = hash.iter().map(|b| format!("{b:02x}")).collect();
+    format!("0x{hex}")
+}
+
+/// Parse a 32-byte hash from hex, with or without the `0x` prefix.
+pub fn parse_hash(s: &str) -> Result<[u8; 32]> {
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    if hex.len() != 64 {
+        anyhow::bail!("Invalid hash (expected 32 bytes of hex): {s}");
+    }
+    // `from_str_radix` alone would also take a `+` sign.
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        anyhow::bail!("Invalid hash (not hex): {s}");
+    }
+    let mut hash = [0u8; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)?;
+    }
+    Ok(hash)
 }
 
 /// Format an integer with thousands separators: `339736627` -> `339,736,627`.
-fn group_thousands(n: u64) -> String {
+pub(crate) fn group_thousands(n: u64) -> String {
     let s = n.to_string();
     let len = s.len();
     let mut out = String::with_capacity(len + len / 3);
@@ -267,6 +455,9 @@ fn execute_metrics(time_ms: u64, steps: u64, cost: Option<u64>) -> ZiskExecution
         cost,
         tx_count: None,
         gas_used: None,
+        chain: None,
+        block_number: None,
+        committed_block_hash: None,
     }
 }
 
@@ -297,42 +488,34 @@ fn log_plan(output: &ExecuteOutput) {
     info!("-----------------");
 }
 
-#[expect(dead_code)]
-fn parse_metrics(output: &str) -> Result<ZiskExecutionMetrics> {
-    let mut steps = 0u64;
-    let mut cost = 0u64;
-    let mut tx_count = None;
-    let mut gas_used = None;
-
-    for line in output.lines() {
-        if line.contains("STEPS")
-            && let Some(val) = line.split_whitespace().last()
-        {
-            steps = val.replace(",", "").parse().unwrap_or(0);
-        }
-        if line.contains("TOTAL")
-            && line.contains("100.00%")
-            && let Some(val) = line.split_whitespace().nth(1)
-        {
-            cost = val.replace(",", "").parse().unwrap_or(0);
-        }
-        if line.contains("- Transaction Count:")
-            && let Some(val) = line.split(':').next_back()
-        {
-            tx_count = val.trim().replace(",", "").parse().ok();
-        }
-        if line.contains("- Gas Consumed:")
-            && let Some(val) = line.split(':').next_back()
-        {
-            gas_used = val.trim().replace(",", "").parse().ok();
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "00112233445566778899aabbccddeeff00112233445566778899AABBCCDDEEFF";
+
+    #[test]
+    fn parses_hashes_with_or_without_prefix() {
+        let hash = parse_hash(HEX).unwrap();
+        assert_eq!(hash[..4], [0x00, 0x11, 0x22, 0x33]);
+        assert_eq!(hash[28..], [0xcc, 0xdd, 0xee, 0xff]);
+        assert_eq!(parse_hash(&format!("0x{HEX}")).unwrap(), hash);
+        assert_eq!(parse_hash(&format_hash(&hash)).unwrap(), hash);
     }
 
-    Ok(ZiskExecutionMetrics {
-        duration: Duration::default(),
-        steps,
-        cost: Some(cost),
-        tx_count,
-        gas_used,
-    })
+    #[test]
+    fn rejects_malformed_hashes() {
+        for bad in [
+            String::new(),
+            "0x".to_string(),
+            HEX[..62].to_string(),
+            format!("{HEX}00"),
+            format!("0x0x{}", &HEX[..60]),
+            format!("{}zz", &HEX[..62]),
+            format!("+f{}", &HEX[2..]),
+            format!("{}é", &HEX[..62]),
+        ] {
+            assert!(parse_hash(&bad).is_err(), "{bad}");
+        }
+    }
 }
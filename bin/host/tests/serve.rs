@@ -0,0 +1,184 @@
+//! Drives `hints-gen serve`'s protocol over a socket pair, as a prover would.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+};
+
+use host::hints_gen::serve::{Request, Response, ServeOptions, Server, Status};
+use input::Client;
+
+struct Connection {
+    stream: UnixStream,
+    reader: BufReader<UnixStream>,
+}
+
+impl Connection {
+    /// A server on one end of a socket pair, serving until the other closes.
+    fn start() -> Self {
+        Self::start_with(options())
+    }
+
+    fn start_with(options: ServeOptions) -> Self {
+        let (server_end, stream) = UnixStream::pair().expect("socket pair");
+        std::thread::spawn(move || {
+            let server = Server::new(Client::Reth, None, options).expect("server");
+            server.serve_connection(&server_end)
+        });
+        let reader = BufReader::new(stream.try_clone().expect("clone"));
+        Self { stream, reader }
+    }
+
+    fn send(&mut self, request: &Request, payload: &[u8]) -> Response {
+        let mut line = serde_json::to_string(request).expect("request");
+        line.push('\n');
+        self.stream
+            .write_all(line.as_bytes())
+            .expect("send request");
+        self.stream.write_all(payload).expect("send payload");
+        self.receive()
+    }
+
+    fn receive(&mut self) -> Response {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).expect("read response");
+        serde_json::from_str(&line).expect("response")
+    }
+
+    /// Whether the server closed its end.
+    fn closed(&mut self) -> bool {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).expect("read") == 0
+    }
+}
+
+fn sink_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("serve-{}", std::process::id()))
+}
+
+fn options() -> ServeOptions {
+    std::fs::create_dir_all(sink_dir()).expect("sink dir");
+    ServeOptions {
+        sink_dir: sink_dir(),
+        max_input_len: 1 << 30,
+        timeout: None,
+    }
+}
+
+fn reth_input() -> Vec<u8> {
+    let input = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../guests/stateless-validator-reth/inputs/mainnet_24628607_66_7_zec_reth.bin");
+    std::fs::read(input).expect("reth fixture")
+}
+
+/// A sink named relative to the sink dir, as requests name them.
+fn hints_path(name: &str) -> PathBuf {
+    PathBuf::from(format!("{name}.hints"))
+}
+
+#[test]
+fn bad_requests_keep_the_connection() {
+    let mut connection = Connection::start();
+    let input = reth_input();
+
+    // Both input sources: the payload is still consumed, so the next request
+    // on the connection is read correctly.
+    let response = connection.send(
+        &Request {
+            input_len: Some(input.len() as u64),
+            block: Some(1),
+            output: Some(hints_path("both")),
+            ..Default::default()
+        },
+        &input,
+    );
+    assert_eq!(response.status, Status::Error);
+    assert!(response.error.unwrap().contains("exactly one of"));
+
+    // A block without an RPC to fetch it from.
+    let response = connection.send(
+        &Request {
+            block: Some(1),
+            output: Some(hints_path("block")),
+            ..Default::default()
+        },
+        &[],
+    );
+    assert_eq!(response.status, Status::Error);
+    assert!(response.error.unwrap().contains("--rpc-url"));
+
+    // No sink.
+    let response = connection.send(
+        &Request {
+            input_len: Some(input.len() as u64),
+            ..Default::default()
+        },
+        &input,
+    );
+    assert_eq!(response.status, Status::Error);
+    assert!(response.error.unwrap().contains("`output` and `socket`"));
+
+    // Sinks outside the sink dir.
+    for output in ["/tmp/escape.hints", "../escape.hints"] {
+        let response = connection.send(
+            &Request {
+                input_len: Some(input.len() as u64),
+                output: Some(PathBuf::from(output)),
+                ..Default::default()
+            },
+            &input,
+        );
+        assert_eq!(response.status, Status::Error);
+        assert!(response.error.unwrap().contains("--sink-dir"), "{output}");
+    }
+    assert!(!sink_dir().join("../escape.hints").exists());
+}
+
+#[test]
+fn oversized_inputs_end_the_connection() {
+    let mut connection = Connection::start_with(ServeOptions {
+        max_input_len: 16,
+        ..options()
+    });
+    let response = connection.send(
+        &Request {
+            input_len: Some(17),
+            output: Some(hints_path("oversized")),
+            ..Default::default()
+        },
+        &[],
+    );
+    assert_eq!(response.status, Status::Error);
+    assert!(response.error.unwrap().contains("--max-input-len 16"));
+    assert!(connection.closed());
+}
+
+#[cfg(zisk_hints)]
+#[test]
+fn serves_consecutive_blocks() {
+    let mut connection = Connection::start();
+    let input = reth_input();
+
+    // The same block twice on one connection: the second run reuses the warm
+    // client and must produce the same hints.
+    let mut hints = Vec::new();
+    for run in ["first", "second"] {
+        let output = hints_path(run);
+        let response = connection.send(
+            &Request {
+                input_len: Some(input.len() as u64),
+                output: Some(output.clone()),
+                ..Default::default()
+            },
+            &input,
+        );
+        assert_eq!(response.status, Status::Ok, "{:?}", response.error);
+        assert!(response.execution_time.is_some());
+        let output = sink_dir().join(output);
+        hints.push(std::fs::read(&output).expect("hints file"));
+        let _ = std::fs::remove_file(output);
+    }
+    assert!(hints[0].len() > 8, "no hints were written");
+    assert_eq!(hints[0], hints[1]);
+}
@@ -0,0 +1,103 @@
+//! A timed-out request ends `hints-gen serve`: the abandoned run can't be
+//! stopped, so the server answers `timed_out` and stops serving for a
+//! supervisor to restart it. In a file of its own, as the abandoned run
+//! taints hints generation for the rest of the test process.
+#![cfg(zisk_hints)]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use zisk_sdk::ZiskStdin;
+
+use host::hints_gen::{
+    is_timeout,
+    serve::{Request, Response, ServeOptions, Server, Status},
+};
+use input::{BlockStats, ExecutionClient, InputMetadata, RpcConfig};
+
+/// A client whose run outlives any short timeout.
+struct Stuck;
+
+#[async_trait]
+impl ExecutionClient for Stuck {
+    fn name(&self) -> &'static str {
+        "stuck"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Stuck"
+    }
+
+    async fn from_rpc(&self, _: &RpcConfig, _: u64) -> Result<(ZiskStdin, BlockStats)> {
+        anyhow::bail!("no RPC")
+    }
+
+    fn run(&self) {
+        std::thread::sleep(Duration::from_secs(5));
+    }
+
+    fn input_block_hash(&self, _: &[u8]) -> Result<[u8; 32]> {
+        anyhow::bail!("no block hash")
+    }
+
+    fn input_metadata(&self, _: &[u8]) -> Result<InputMetadata> {
+        anyhow::bail!("no metadata")
+    }
+
+    fn committed_block_hash(&self, _: &[u8]) -> Result<[u8; 32]> {
+        anyhow::bail!("no publics")
+    }
+}
+
+#[test]
+fn a_timeout_ends_serving() {
+    let sink_dir = std::env::temp_dir().join(format!("serve-timeout-{}", std::process::id()));
+    std::fs::create_dir_all(&sink_dir).expect("sink dir");
+    let server = Server::with_client(
+        Arc::new(Stuck),
+        None,
+        ServeOptions {
+            sink_dir: sink_dir.clone(),
+            max_input_len: 1 << 20,
+            timeout: Some(Duration::from_secs(1)),
+        },
+    )
+    .expect("server");
+
+    let (server_end, mut stream) = UnixStream::pair().expect("socket pair");
+    let serving = std::thread::spawn(move || server.serve_connection(&server_end));
+
+    // One empty slice: a well-framed input.
+    let payload = 0u64.to_le_bytes();
+    let request = Request {
+        input_len: Some(payload.len() as u64),
+        output: Some(PathBuf::from("stuck.hints")),
+        ..Default::default()
+    };
+    let mut line = serde_json::to_string(&request).expect("request");
+    line.push('\n');
+    stream.write_all(line.as_bytes()).expect("send request");
+    stream.write_all(&payload).expect("send payload");
+    // A second request, which must not be served.
+    stream.write_all(line.as_bytes()).expect("send request");
+    stream.write_all(&payload).expect("send payload");
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).expect("read response");
+    let response: Response = serde_json::from_str(&line).expect("response");
+    assert_eq!(response.status, Status::TimedOut);
+
+    let error = serving.join().expect("server thread").unwrap_err();
+    assert!(is_timeout(&error), "{error:#}");
+    line.clear();
+    assert_eq!(reader.read_line(&mut line).expect("read"), 0, "{line}");
+    let _ = std::fs::remove_dir_all(sink_dir);
+}
@@ -1,4 +1,5 @@
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
@@ -7,7 +8,7 @@ use zisk_sdk::ZiskStdin;
 
 use ethrex_common::types::block_execution_witness::{decode_witness_headers, RpcExecutionWitness};
 use ethrex_common::types::Block;
-use ethrex_common::NativeCrypto;
+use ethrex_common::{NativeCrypto, H256};
 use ethrex_config::networks::Network;
 use ethrex_rpc::types::{block::RpcBlock, block_identifier::BlockIdentifier};
 use ethrex_rpc::EthClient;
@@ -17,7 +18,7 @@ use guest_ethrex::EthrexInput;
 
 pub use guest_ethrex as guest;
 pub use input_core::RpcConfig;
-use input_core::{BlockStats, ExecutionClient};
+use input_core::{stdin_slices, BlockStats, ExecutionClient, InputMetadata};
 
 #[derive(Default)]
 pub struct EthrexClient;
@@ -58,25 +59,28 @@ impl ExecutionClient for EthrexClient {
             }
         }
 
-        let url = Url::parse(&config.url).context("Invalid RPC URL")?;
-        let rpc_client = EthClient::new(url).context("Failed to create EthClient")?;
-
-        let chain_id = rpc_client.get_chain_id().await?.as_u64();
+        let rpc = rpc(config).await?;
+        let chain_id = rpc.chain_id;
         let chain_config = Network::try_from(chain_id)
             .map_err(|e| anyhow::anyhow!("Unsupported chain ID {chain_id}: {e}"))?
             .get_genesis()
             .context("Failed to get genesis config")?
             .config;
 
-        let rpc_block: RpcBlock = rpc_client
-            .get_block_by_number(BlockIdentifier::Number(block_number), true)
-            .await
-            .with_context(|| format!("Failed to fetch block {block_number}"))?;
-
-        let rpc_witness: RpcExecutionWitness = rpc_client
-            .get_witness(BlockIdentifier::Number(block_number), None)
-            .await
-            .with_context(|| format!("Failed to fetch witness for block {block_number}"))?;
+        let (rpc_block, rpc_witness): (RpcBlock, RpcExecutionWitness) = futures::try_join!(
+            config.request("eth_getBlockByNumber", || async {
+                rpc.client
+                    .get_block_by_number(BlockIdentifier::Number(block_number), true)
+                    .await
+                    .with_context(|| format!("Failed to fetch block {block_number}"))
+            }),
+            config.request("debug_executionWitness", || async {
+                rpc.client
+                    .get_witness(BlockIdentifier::Number(block_number), None)
+                    .await
+                    .with_context(|| format!("Failed to fetch witness for block {block_number}"))
+            }),
+        )?;
 
         let block: Block = rpc_block
             .try_into()
@@ -105,4 +109,57 @@ impl ExecutionClient for EthrexClient {
     fn run(&self) {
         guest_ethrex::run();
     }
+
+    fn input_block_hash(&self, input: &[u8]) -> Result<[u8; 32]> {
+        let input = input_from_bytes(input)?;
+        Ok(input.block.hash().0)
+    }
+
+    fn input_metadata(&self, input: &[u8]) -> Result<InputMetadata> {
+        let input = input_from_bytes(input)?;
+        let header = &input.block.header;
+        Ok(InputMetadata {
+            chain_name: Some(chain_name(input.execution_witness.chain_config.chain_id)),
+            block_number: header.number,
+            tx_count: input.block.body.transactions.len() as u64,
+            gas_used: Some(header.gas_used),
+        })
+    }
+
+    /// The guest commits an `H256`, bincode-encoded like the rest of its I/O.
+    fn committed_block_hash(&self, publics: &[u8]) -> Result<[u8; 32]> {
+        let (hash, _): (H256, _) =
+            bincode::serde::decode_from_slice(publics, bincode::config::standard())
+                .context("Failed to decode committed block hash")?;
+        Ok(hash.0)
+    }
+}
+
+/// Decode a saved ethrex input: one slice holding the rkyv-encoded `EthrexInput`.
+fn input_from_bytes(input: &[u8]) -> Result<EthrexInput> {
+    let slices = stdin_slices(input)?;
+    let bytes = slices.first().context("Input has no slices")?;
+    EthrexInput::deserialize(bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize EthrexInput: {e}"))
+}
+
+/// What ethrex keeps per endpoint across blocks: the client and the chain ID
+/// (an endpoint serves a single chain).
+struct EthrexRpc {
+    client: EthClient,
+    chain_id: u64,
+}
+
+async fn rpc(config: &RpcConfig) -> Result<Arc<EthrexRpc>> {
+    config
+        .connection(|| async {
+            let url = Url::parse(&config.url).context("Invalid RPC URL")?;
+            let client = EthClient::new(url).context("Failed to create EthClient")?;
+            let chain_id = config
+                .request("eth_chainId", || async { Ok(client.get_chain_id().await?) })
+                .await?
+                .as_u64();
+            Ok(EthrexRpc { client, chain_id })
+        })
+        .await
 }
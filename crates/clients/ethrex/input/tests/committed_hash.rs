@@ -0,0 +1,18 @@
+//! The publics the ethrex guest commits: its block hash, bincode-encoded.
+
+use ethrex_common::H256;
+use input_core::ExecutionClient;
+use input_ethrex::EthrexClient;
+
+#[test]
+fn committed_block_hash_round_trips_the_guest_encoding() {
+    let hash = H256(std::array::from_fn(|i| 0xff - i as u8));
+    let publics = bincode::serde::encode_to_vec(hash, bincode::config::standard()).unwrap();
+    assert_eq!(
+        EthrexClient.committed_block_hash(&publics).expect("hash"),
+        hash.0
+    );
+    assert!(EthrexClient
+        .committed_block_hash(&publics[..publics.len() - 1])
+        .is_err());
+}
@@ -1,4 +1,5 @@
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
 use anyhow::{Context, Result};
@@ -7,7 +8,8 @@ use tracing::debug;
 use url::Url;
 
 use alloy_genesis::ChainConfig;
-use alloy_provider::{ext::DebugApi, Provider, ProviderBuilder};
+use alloy_primitives::B256;
+use alloy_provider::{ext::DebugApi, Provider, RootProvider};
 use alloy_rpc_client::RpcClient;
 use alloy_rpc_types_debug::ExecutionWitness;
 use alloy_rpc_types_eth::Block as RpcBlock;
@@ -28,7 +30,7 @@ use guest_reth::{RethInput, RethInputPublic, RethInputWitness};
 
 pub use guest_reth as guest;
 pub use input_core::RpcConfig;
-use input_core::{BlockStats, ExecutionClient};
+use input_core::{stdin_slices, BlockStats, ExecutionClient, InputMetadata};
 
 #[derive(Default)]
 pub struct RethClient;
@@ -47,8 +49,12 @@ impl RethClient {
         config: &RpcConfig,
         block_number: u64,
     ) -> Result<RethInputWitness> {
-        let provider = connect_provider(config).await?;
-        let witness = fetch_witness(&provider, block_number).await?;
+        let rpc = rpc(config).await?;
+        let witness = config
+            .request("debug_executionWitness", || {
+                fetch_witness(&rpc.provider, block_number)
+            })
+            .await?;
         Ok(RethInputWitness::new(witness))
     }
 
@@ -57,11 +63,14 @@ impl RethClient {
         config: &RpcConfig,
         block_number: u64,
     ) -> Result<RethInputPublic> {
-        let provider = connect_provider(config).await?;
-        let chain_config = fetch_chain_config(&provider).await?;
-        let block = fetch_block(&provider, block_number).await?;
+        let rpc = rpc(config).await?;
+        let block = config
+            .request("eth_getBlockByNumber", || {
+                fetch_block(&rpc.provider, block_number)
+            })
+            .await?;
 
-        RethInputPublic::new(block.into(), chain_config)
+        RethInputPublic::new(block.into(), rpc.chain_config.clone())
             .with_context(|| format!("Failed to build RethInputPublic for block {block_number}"))
     }
 
@@ -101,10 +110,16 @@ impl ExecutionClient for RethClient {
         config: &RpcConfig,
         block_number: u64,
     ) -> Result<(ZiskStdin, BlockStats)> {
-        let provider = connect_provider(config).await?;
-        let chain_config = fetch_chain_config(&provider).await?;
-        let block = fetch_block(&provider, block_number).await?;
-        let witness = fetch_witness(&provider, block_number).await?;
+        let rpc = rpc(config).await?;
+        let chain_config = rpc.chain_config.clone();
+        let (block, witness) = futures::try_join!(
+            config.request("eth_getBlockByNumber", || {
+                fetch_block(&rpc.provider, block_number)
+            }),
+            config.request("debug_executionWitness", || {
+                fetch_witness(&rpc.provider, block_number)
+            }),
+        )?;
 
         let stats = BlockStats {
             chain_name: chain_name(chain_config.chain_id),
@@ -127,9 +142,62 @@ impl ExecutionClient for RethClient {
     fn run(&self) {
         guest_reth::run();
     }
+
+    fn input_block_hash(&self, input: &[u8]) -> Result<[u8; 32]> {
+        let public = public_from_input(input)?;
+        Ok(public.block.header.hash_slow().0)
+    }
+
+    fn input_metadata(&self, input: &[u8]) -> Result<InputMetadata> {
+        let public = public_from_input(input)?;
+        let header = &public.block.header;
+        Ok(InputMetadata {
+            chain_name: Some(chain_name(public.chain_config.chain_id)),
+            block_number: header.number,
+            tx_count: public.block.body.transactions.len() as u64,
+            gas_used: Some(header.gas_used),
+        })
+    }
+
+    /// The guest commits a `B256`, bincode-encoded like the rest of its I/O.
+    fn committed_block_hash(&self, publics: &[u8]) -> Result<[u8; 32]> {
+        let (hash, _): (B256, _) =
+            bincode::serde::decode_from_slice(publics, bincode::config::standard())
+                .context("Failed to decode committed block hash")?;
+        Ok(hash.0)
+    }
+}
+
+/// Decode the public half (first slice) of a saved reth input.
+fn public_from_input(input: &[u8]) -> Result<RethInputPublic> {
+    let slices = stdin_slices(input)?;
+    let public = slices.first().context("Input has no public slice")?;
+    RethInputPublic::deserialize(public)
+}
+
+/// What reth keeps per endpoint across blocks: the connection, and the chain
+/// config (an endpoint serves a single chain).
+struct RethRpc {
+    provider: RootProvider,
+    chain_config: ChainConfig,
+}
+
+async fn rpc(config: &RpcConfig) -> Result<Arc<RethRpc>> {
+    config
+        .connection(|| async {
+            let provider = connect_provider(config).await?;
+            let chain_config = config
+                .request("eth_chainId", || fetch_chain_config(&provider))
+                .await?;
+            Ok(RethRpc {
+                provider,
+                chain_config,
+            })
+        })
+        .await
 }
 
-async fn connect_provider(config: &RpcConfig) -> Result<impl Provider + DebugApi> {
+async fn connect_provider(config: &RpcConfig) -> Result<RootProvider> {
     let url: Url = config.url.parse().context("Invalid RPC URL")?;
 
     let mut header_map = HeaderMap::new();
@@ -149,7 +217,7 @@ async fn connect_provider(config: &RpcConfig) -> Result<impl Provider + DebugApi
     let start = Instant::now();
     let http = Http::with_client(http_client, url);
     let rpc_client = RpcClient::new(http, false);
-    let provider = ProviderBuilder::new().connect_client(rpc_client);
+    let provider = RootProvider::new(rpc_client);
     debug!("RPC connect time: {:?}", start.elapsed());
     Ok(provider)
 }
@@ -11,7 +11,8 @@
 //! [`run`] executes ziskethone's C++ block validation natively, in-process, via
 //! the `zeg_run` FFI (zilkworm-style) — the same pipeline the ZisK ELF runs, but
 //! on the host CPU for fast input checking. Its native lib is compiled from the
-//! ziskethone submodule with a normal C++ toolchain (not the RISC-V one).
+//! ziskethone submodule with a normal C++ toolchain (not the RISC-V one). It
+//! emits no ZisK hints yet ([`EMITS_HINTS`]).
 
 use zisk_sdk::{load_program, GuestProgram};
 
@@ -49,6 +50,12 @@ mod ffi {
 #[cfg(feature = "native-ffi")]
 pub use ffi::run;
 
+/// Whether [`run`] emits ZisK hints. Not yet: the native C++ EVM calls
+/// evmone/blst directly rather than ZisK's `zkvm_accelerators.h` ABI, so
+/// nothing records a hint. Flip this once `cpp-guest` can route those calls
+/// through ziskos in its native build.
+pub const EMITS_HINTS: bool = false;
+
 /// Stub for builds without `native-ffi`: the C++ lib isn't compiled, so there's
 /// nothing to call. Lets consumers build without the C++ toolchain (input
 /// generation / client creation never call `run`); panics with a remedy rather
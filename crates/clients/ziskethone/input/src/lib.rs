@@ -1,6 +1,6 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Context, Result};
 use async_trait::async_trait;
 use guest_common::chain::chain_name;
 use zisk_sdk::ZiskStdin;
@@ -8,12 +8,28 @@ use zisk_sdk::ZiskStdin;
 pub use guest_ziskethone as guest;
 
 pub use input_core::RpcConfig;
-use input_core::{BlockStats, ExecutionClient};
+use input_core::{stdin_slices, BlockStats, ExecutionClient, InputMetadata};
 
 /// Default ancestor depth for the PreviousBlocks section. The EVM BLOCKHASH
 /// opcode reaches back 256, matching `rust-input-gen`'s CLI default.
 const DEFAULT_ANCESTORS: u64 = 256;
 
+/// The ZEG0 layout `input_metadata` reads (format v8/v9; see
+/// `tools/ziskethone-to-reth`'s reader): the magic + version header, then the
+/// fixed ConsensusInfo prefix, then the withdrawal records, then the
+/// transactions section, which opens with its count.
+const ZEG0_HEADER_LEN: usize = 4 + 4;
+const ZEG0_CONSENSUS_INFO_LEN: usize = 368;
+/// `number`, after `parent_state_root` and the padded `beneficiary`.
+const ZEG0_BLOCK_NUMBER: usize = ZEG0_HEADER_LEN + 32 + (20 + 4);
+/// `withdrawals_count`, after `number`, `gas_limit`, `timestamp`,
+/// `extra_data_len`, the 32-byte `extra_data` buffer, `prev_randao`,
+/// `parent_beacon_block_root` and `base_fee`.
+const ZEG0_WITHDRAWALS_COUNT: usize = ZEG0_BLOCK_NUMBER + 4 * 8 + 32 + 3 * 32;
+const ZEG0_WITHDRAWALS: usize = ZEG0_HEADER_LEN + ZEG0_CONSENSUS_INFO_LEN;
+/// `index`, `validator_index`, the padded `address` and `amount`.
+const ZEG0_WITHDRAWAL_LEN: usize = 8 + 8 + (20 + 4) + 8;
+
 #[derive(Default)]
 pub struct ZiskEthOneClient;
 
@@ -42,13 +58,25 @@ impl ExecutionClient for ZiskEthOneClient {
             }
         }
 
-        let (bytes, s) = rust_input_gen::live::fetch_and_build_with_stats(
-            &config.url,
-            block_number,
-            DEFAULT_ANCESTORS,
-        )
-        .await
-        .map_err(|e| anyhow!("ziskethone input generation failed: {e}"))?;
+        // rust-input-gen makes its requests (the block, its witness and up to
+        // DEFAULT_ANCESTORS headers) over a client of its own, which takes
+        // neither our rate limiter nor a timeout. So the whole fetch is one
+        // request to us: a retry refetches the block, the rate limit counts
+        // it once, and the per-request timeout, sized for a single request,
+        // doesn't apply to it.
+        let mut fetch = config.clone();
+        fetch.retry.request_timeout = None;
+        let (bytes, s) = fetch
+            .request("ziskethone fetch", || async {
+                rust_input_gen::live::fetch_and_build_with_stats(
+                    &config.url,
+                    block_number,
+                    DEFAULT_ANCESTORS,
+                )
+                .await
+                .map_err(|e| anyhow!("ziskethone input generation failed: {e}"))
+            })
+            .await?;
 
         let stdin = ZiskStdin::new();
         stdin.write_slice(&bytes);
@@ -76,10 +104,57 @@ impl ExecutionClient for ZiskEthOneClient {
         tracing::info!("ziskethone run complete; execution block hash: 0x{hex}");
     }
 
+    /// Always an error: ZEG0 carries the parent's state root but not the
+    /// current block's header hash (its execution outputs are recomputed by
+    /// the guest), so there is nothing to read it from. Callers check a
+    /// ziskethone run's committed hash only against one passed explicitly
+    /// (`--expect-hash`).
+    fn input_block_hash(&self, _input: &[u8]) -> Result<[u8; 32]> {
+        Err(anyhow!(
+            "ziskethone inputs do not carry the block hash; pass it explicitly"
+        ))
+    }
+
+    /// ZEG0 carries neither the chain id nor the block's gas used (an execution
+    /// output the guest recomputes), so only the block number and tx count are
+    /// known.
+    fn input_metadata(&self, input: &[u8]) -> Result<InputMetadata> {
+        let slices = stdin_slices(input)?;
+        let zeg0 = slices.first().context("Input has no slices")?;
+        ensure!(zeg0.starts_with(b"ZEG0"), "Input is not a ZEG0 container");
+
+        let withdrawals = read_u64(zeg0, ZEG0_WITHDRAWALS_COUNT)? as usize;
+        let tx_count_at = withdrawals
+            .checked_mul(ZEG0_WITHDRAWAL_LEN)
+            .and_then(|len| len.checked_add(ZEG0_WITHDRAWALS))
+            .context("ZEG0 withdrawals count overflows")?;
+        Ok(InputMetadata {
+            chain_name: None,
+            block_number: read_u64(zeg0, ZEG0_BLOCK_NUMBER)?,
+            tx_count: read_u64(zeg0, tx_count_at)?,
+            gas_used: None,
+        })
+    }
+
+    /// The C++ guest commits the raw 32-byte block hash.
+    fn committed_block_hash(&self, publics: &[u8]) -> Result<[u8; 32]> {
+        publics
+            .get(..32)
+            .and_then(|h| h.try_into().ok())
+            .ok_or_else(|| anyhow!("public outputs too short for a block hash"))
+    }
+
     /// `run()` runs the C++ EVM over FFI as a native input checker; it does not
-    /// emit ZisK hints. The hints harness rejects this client rather than
-    /// writing an empty hints file.
+    /// emit ZisK hints yet (see [`guest_ziskethone::EMITS_HINTS`]). The hints
+    /// harness rejects this client rather than writing an empty hints file.
     fn emits_hints(&self) -> bool {
-        false
+        guest_ziskethone::EMITS_HINTS
     }
 }
+
+fn read_u64(buf: &[u8], offset: usize) -> Result<u64> {
+    let bytes = buf
+        .get(offset..offset + 8)
+        .with_context(|| format!("Truncated ZEG0 container: no u64 at offset {offset}"))?;
+    Ok(u64::from_le_bytes(bytes.try_into().expect("8-byte slice")))
+}
@@ -0,0 +1,52 @@
+//! Decodes `fixtures/zeg0_prefix.bin`: a `ZiskStdin` slice holding the start of
+//! a v9 ZEG0 container — the header, a ConsensusInfo prefix for block
+//! 24628607 with two withdrawals, and a transactions section that stops after
+//! its count of 66. That is all `input_metadata` reads.
+
+use input_core::ExecutionClient;
+use input_ziskethone::ZiskEthOneClient;
+
+const FIXTURE: &[u8] = include_bytes!("fixtures/zeg0_prefix.bin");
+
+#[test]
+fn metadata_from_zeg0_prefix() {
+    let metadata = ZiskEthOneClient.input_metadata(FIXTURE).expect("metadata");
+    assert_eq!(metadata.block_number, 24628607);
+    assert_eq!(metadata.tx_count, 66);
+    assert_eq!(metadata.chain_name, None);
+    assert_eq!(metadata.gas_used, None);
+}
+
+#[test]
+fn truncated_zeg0_is_rejected() {
+    // Cut the container before the tx count, re-framed as a 472-byte slice.
+    let mut truncated = FIXTURE[8..8 + 472].to_vec();
+    let mut input = (truncated.len() as u64).to_le_bytes().to_vec();
+    input.append(&mut truncated);
+    let error = ZiskEthOneClient.input_metadata(&input).unwrap_err();
+    assert!(error.to_string().contains("offset 472"), "{error:#}");
+}
+
+#[test]
+fn non_zeg0_input_is_rejected() {
+    let mut input = FIXTURE.to_vec();
+    input[8..12].copy_from_slice(b"ZEG1");
+    assert!(ZiskEthOneClient.input_metadata(&input).is_err());
+}
+
+#[test]
+fn block_hash_is_not_carried() {
+    assert!(ZiskEthOneClient.input_block_hash(FIXTURE).is_err());
+}
+
+#[test]
+fn committed_block_hash_is_the_raw_publics() {
+    let publics: Vec<u8> = (0..40).collect();
+    let hash = ZiskEthOneClient
+        .committed_block_hash(&publics)
+        .expect("hash");
+    assert_eq!(hash.as_slice(), &publics[..32]);
+    assert!(ZiskEthOneClient
+        .committed_block_hash(&publics[..31])
+        .is_err());
+}
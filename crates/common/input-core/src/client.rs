@@ -1,20 +1,44 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+};
+
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use tokio::sync::OnceCell;
+use tracing::warn;
 use zisk_sdk::ZiskStdin;
 
+use crate::retry::{transient, RateLimiter, RequestTimeout, RetryPolicy, Transient};
+
 /// Headers are honored only by clients that support custom HTTP headers
 /// (currently `reth`). Others warn once and ignore them.
+///
+/// A config also keeps each client's connection to the endpoint (see
+/// [`connection`](Self::connection)), shared by its clones, so a run that
+/// fetches many blocks through one config connects once. Its requests go
+/// through [`request`](Self::request), under the config's retry policy and
+/// rate limit.
 #[derive(Debug, Clone, Default)]
 pub struct RpcConfig {
     pub url: String,
     pub headers: Vec<(String, String)>,
+    pub retry: RetryPolicy,
+    /// Shared by the clones, so the cap holds across concurrent blocks.
+    rate_limit: Option<Arc<RateLimiter>>,
+    connections: Arc<Mutex<Connections>>,
 }
 
+/// Client state per endpoint, keyed by its type so each client finds its own.
+type Connections = HashMap<TypeId, Arc<OnceCell<Arc<dyn Any + Send + Sync>>>>;
+
 impl RpcConfig {
     pub fn new(url: impl Into<String>) -> Self {
         Self {
             url: url.into(),
-            headers: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -22,6 +46,93 @@ impl RpcConfig {
         self.headers = headers;
         self
     }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Cap the requests made through this config (and its clones) to
+    /// `per_second`, raised to [`MIN_RATE_LIMIT`](crate::MIN_RATE_LIMIT) if lower.
+    pub fn with_rate_limit(mut self, per_second: f64) -> Self {
+        self.rate_limit = Some(Arc::new(RateLimiter::new(per_second)));
+        self
+    }
+
+    /// Make one RPC request (`method` names it in logs) under the retry policy
+    /// and rate limit: each attempt waits for its slot and times out after
+    /// `request_timeout`; transient failures are retried with backoff, and
+    /// rate-limited ones back off longer.
+    pub async fn request<T, F, Fut>(&self, method: &str, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            if let Some(rate_limit) = &self.rate_limit {
+                rate_limit.acquire().await;
+            }
+            let result = match self.retry.request_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, request())
+                    .await
+                    .unwrap_or_else(|_| Err(RequestTimeout(timeout).into())),
+                None => request().await,
+            };
+            let error = match result {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let Some(kind) = transient(&error) else {
+                return Err(error);
+            };
+            if attempt >= self.retry.max_retries {
+                return Err(
+                    error.context(format!("{method} failed after {} attempt(s)", attempt + 1))
+                );
+            }
+
+            // A rate limit starts the backoff two doublings in.
+            let (delay, reason) = match kind {
+                Transient::RateLimited => (self.retry.backoff(attempt + 2), "rate-limited"),
+                Transient::Other => (self.retry.backoff(attempt), "failed"),
+            };
+            attempt += 1;
+            warn!(
+                "{method} {reason} ({error:#}); retry {attempt}/{} in {delay:.1?}",
+                self.retry.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// The client state of type `T` kept for this endpoint — its connection,
+    /// and whatever else doesn't change from block to block — created by
+    /// `connect` on first use. Concurrent first uses wait for one `connect`;
+    /// a failed one is retried by the next use.
+    pub async fn connection<T, F, Fut>(&self, connect: F) -> Result<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let cell = Arc::clone(
+            self.connections
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(TypeId::of::<T>())
+                .or_default(),
+        );
+        let state = cell
+            .get_or_try_init(|| async {
+                let state: Arc<dyn Any + Send + Sync> = Arc::new(connect().await?);
+                anyhow::Ok(state)
+            })
+            .await?;
+        Ok(Arc::clone(state)
+            .downcast::<T>()
+            .unwrap_or_else(|_| unreachable!("connections are keyed by their type")))
+    }
 }
 
 /// Parse a `Key:Value` header pair (used by clap as a `value_parser`).
@@ -54,6 +165,81 @@ impl BlockStats {
     }
 }
 
+/// The fields of an input filename written by [`BlockStats::output_filename`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputFileName {
+    pub chain: String,
+    pub block_number: u64,
+    pub tx_count: u64,
+    /// Gas used in whole Mgas (rounded down).
+    pub mgas: u64,
+    pub client: String,
+}
+
+impl InputFileName {
+    /// Parse `<chain>_<block>_<txs>_<mgas>_zec_<client>`, with any extension.
+    /// `None` for names not in that form (e.g. inputs built from EEST fixtures).
+    pub fn parse(file_name: &str) -> Option<Self> {
+        let stem = file_name
+            .split_once('.')
+            .map_or(file_name, |(stem, _)| stem);
+        let (block_part, client) = stem.rsplit_once("_zec_")?;
+        let mut fields = block_part.rsplitn(4, '_');
+        let mgas = fields.next()?.parse().ok()?;
+        let tx_count = fields.next()?.parse().ok()?;
+        let block_number = fields.next()?.parse().ok()?;
+        let chain = fields.next()?;
+        Some(Self {
+            chain: chain.to_string(),
+            block_number,
+            tx_count,
+            mgas,
+            client: client.to_string(),
+        })
+    }
+}
+
+/// Block metadata decoded from a saved input, so results can be tagged
+/// without parsing filenames. Fields an input format does not carry are `None`.
+#[derive(Debug, Clone, Default)]
+pub struct InputMetadata {
+    pub chain_name: Option<&'static str>,
+    pub block_number: u64,
+    pub tx_count: u64,
+    pub gas_used: Option<u64>,
+}
+
+/// Split a saved `ZiskStdin` (an input `.bin`) into its slices: each is a u64-le
+/// length, the payload, then zero padding to the next 8-byte boundary.
+pub fn stdin_slices(buf: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut slices = Vec::new();
+    let mut cur = 0usize;
+    while cur < buf.len() {
+        let header = buf
+            .get(cur..cur.saturating_add(8))
+            .context("Truncated ZiskStdin length prefix")?;
+        let len = u64::from_le_bytes(header.try_into().expect("8-byte slice"));
+        let overrun = || {
+            format!(
+                "ZiskStdin slice of {len} B overruns the {} B input",
+                buf.len()
+            )
+        };
+        // The length comes from the file (or the network, for `hints-gen
+        // serve`), so every offset derived from it is checked.
+        let start = cur + 8;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .with_context(overrun)?;
+        let payload = buf.get(start..end).with_context(overrun)?;
+        slices.push(payload);
+        let padding = (8 - end % 8) % 8;
+        cur = end.saturating_add(padding);
+    }
+    Ok(slices)
+}
+
 #[async_trait]
 pub trait ExecutionClient: Send + Sync {
     /// Slug used for output filenames and the default `<client>-inputs` output
@@ -75,6 +261,22 @@ pub trait ExecutionClient: Send + Sync {
 
     fn run(&self);
 
+    /// Hash of the block carried by an input file (`input` is the raw `.bin`
+    /// contents). Lets callers check a run committed the block it was given.
+    /// Errors for input formats without the block header (ziskethone's ZEG0
+    /// never carries it), so callers must treat an error as "no hash to check"
+    /// rather than a bad input.
+    fn input_block_hash(&self, input: &[u8]) -> Result<[u8; 32]>;
+
+    /// Chain, block number, tx count and gas used of the block carried by an
+    /// input file (`input` is the raw `.bin` contents).
+    fn input_metadata(&self, input: &[u8]) -> Result<InputMetadata>;
+
+    /// Decode the block hash the guest committed with `ziskos::io::commit` from
+    /// a run's public outputs. The encoding follows the guest's hash type, so
+    /// each client decodes its own.
+    fn committed_block_hash(&self, publics: &[u8]) -> Result<[u8; 32]>;
+
     /// Whether [`run`](Self::run) emits ZisK hints. `true` for instrumented
     /// guest runs (reth, ethrex); `false` for native-only clients like
     /// `ziskethone` whose `run()` is a C++ input checker. The hints harness
@@ -83,3 +285,97 @@ pub trait ExecutionClient: Send + Sync {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A saved `ZiskStdin` holding `slices`.
+    fn stdin(slices: &[&[u8]]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for slice in slices {
+            buf.extend_from_slice(&(slice.len() as u64).to_le_bytes());
+            buf.extend_from_slice(slice);
+            buf.resize(buf.len().next_multiple_of(8), 0);
+        }
+        buf
+    }
+
+    #[test]
+    fn slices_round_trip() {
+        let slices: [&[u8]; 3] = [b"abc", b"", b"12345678"];
+        assert_eq!(stdin_slices(&stdin(&slices)).unwrap(), slices);
+    }
+
+    #[test]
+    fn truncated_slices_are_rejected() {
+        let buf = stdin(&[b"abcdefgh"]);
+        let error = stdin_slices(&buf[..12]).unwrap_err().to_string();
+        assert!(error.contains("overruns"), "{error}");
+        let error = stdin_slices(&buf[..4]).unwrap_err().to_string();
+        assert!(error.contains("Truncated"), "{error}");
+    }
+
+    #[test]
+    fn huge_length_prefix_is_an_error() {
+        for len in [u64::MAX, u64::MAX - 7, usize::MAX as u64 - 8] {
+            let mut buf = len.to_le_bytes().to_vec();
+            buf.extend_from_slice(&[0; 16]);
+            let error = stdin_slices(&buf).unwrap_err().to_string();
+            assert!(error.contains("overruns"), "{len}: {error}");
+        }
+    }
+
+    #[test]
+    fn input_file_names_round_trip() {
+        let stats = BlockStats {
+            chain_name: "Mainnet",
+            block_number: 24628607,
+            tx_count: 66,
+            gas_used: 7_999_999,
+        };
+        let name = InputFileName::parse(&stats.output_filename("reth")).unwrap();
+        assert_eq!(
+            name,
+            InputFileName {
+                chain: "mainnet".to_string(),
+                block_number: 24628607,
+                tx_count: 66,
+                mgas: 7,
+                client: "reth".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn input_file_names_parse_with_any_extension() {
+        for file_name in [
+            "sepolia_1_2_3_zec_ethrex",
+            "sepolia_1_2_3_zec_ethrex.hints",
+            "sepolia_1_2_3_zec_ethrex.hints.rerun",
+        ] {
+            let name = InputFileName::parse(file_name).unwrap();
+            assert_eq!((name.chain.as_str(), name.block_number), ("sepolia", 1));
+            assert_eq!((name.tx_count, name.mgas), (2, 3));
+            assert_eq!(name.client, "ethrex");
+        }
+        // The chain is whatever precedes the four numeric fields.
+        let name = InputFileName::parse("my_devnet_1_2_3_zec_reth.bin").unwrap();
+        assert_eq!(name.chain, "my_devnet");
+    }
+
+    #[test]
+    fn other_file_names_do_not_parse() {
+        for file_name in [
+            "",
+            "block.bin",
+            "mainnet_1_2_3_reth.bin",
+            "mainnet_1_2_zec_reth.bin",
+            "1_2_3_zec_reth.bin",
+            "mainnet_x_2_3_zec_reth.bin",
+            "mainnet_1_2_-3_zec_reth.bin",
+        ] {
+            assert_eq!(InputFileName::parse(file_name), None, "{file_name}");
+        }
+    }
+}
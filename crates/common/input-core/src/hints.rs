@@ -3,14 +3,37 @@
 //! for streaming them to a live prover). Lives next to [`ExecutionClient`] so any
 //! consumer of the trait can generate hints without depending on the host crate.
 
+#[cfg(zisk_hints)]
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
+#[cfg(zisk_hints)]
+use anyhow::Context;
 use anyhow::Result;
 use zisk_sdk::ZiskStdin;
 
 use crate::ExecutionClient;
 
+/// A client run that outlived its hints-generation timeout. The sink is left
+/// open to the abandoned run, which may still be writing to it, so the hints
+/// written before the timeout are incomplete.
+#[derive(Debug, Clone, Copy)]
+pub struct HintsTimeout(pub Duration);
+
+impl std::fmt::Display for HintsTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hints generation timed out after {:.2?}", self.0)
+    }
+}
+
+impl std::error::Error for HintsTimeout {}
+
+/// A hints file starts with a `u64` header (0) before any hint.
+#[cfg(zisk_hints)]
+const HINTS_START_HEADER_LEN: u64 = 8;
+
 #[cfg(zisk_hints)]
 fn hints_pool() -> Result<&'static rayon::ThreadPool> {
     use std::sync::OnceLock;
@@ -28,17 +51,36 @@ fn hints_pool() -> Result<&'static rayon::ThreadPool> {
     Ok(POOL.get().expect("pool was just set"))
 }
 
+/// Set when a run outlives its timeout. A native run can't be interrupted, so
+/// its thread keeps writing to the process-global native input and hints sink
+/// until it returns; any later run in this process could get its hints mixed
+/// in, so those fail instead.
+#[cfg(zisk_hints)]
+static ABANDONED_RUN: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
 /// Shared core: feed `stdin` as the native input, run `client.run()` under
 /// `catch_unwind` with the sink opened by `init`, then tear it down with `deinit`.
-/// `deinit` always runs — even if the client panics — so the sink flushes and
-/// closes. Returns `(execution, total)` durations.
+/// `deinit` runs even if the client panics, so the sink flushes and closes. A
+/// run that outlives `timeout` is abandoned without `deinit`: it would tear the
+/// sink down under the native run still writing to it. Returns `(execution,
+/// total)` durations.
 #[cfg(zisk_hints)]
 fn run_with_hints(
     stdin: &ZiskStdin,
-    client: &dyn ExecutionClient,
+    client: &Arc<dyn ExecutionClient>,
+    timeout: Option<Duration>,
     init: impl FnOnce() -> Result<()> + Send,
     deinit: impl FnOnce() -> Result<()>,
 ) -> Result<(Duration, Duration)> {
+    use std::sync::mpsc::{self, RecvTimeoutError};
+
+    if ABANDONED_RUN.load(std::sync::atomic::Ordering::SeqCst) {
+        anyhow::bail!(
+            "an earlier hints run timed out and is still running in this process; \
+             its hints would mix with this run's, so hints generation needs a new process"
+        );
+    }
+
     ziskos::set_native_input(stdin.read_data());
 
     let pool = hints_pool()?;
@@ -48,12 +90,34 @@ fn run_with_hints(
     // run on the same pool thread that runs the client below.
     pool.install(|| init())?;
 
+    // Spawned rather than installed so a run past `timeout` can be abandoned;
+    // the job owns its client for the same reason.
     let t0 = std::time::Instant::now();
-    let run_result =
-        pool.install(|| std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| client.run())));
+    let (tx, rx) = mpsc::channel();
+    let run_client = Arc::clone(client);
+    pool.spawn(move || {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run_client.run()));
+        let _ = tx.send(result);
+    });
+    let run_result = match timeout {
+        Some(timeout) => rx.recv_timeout(timeout),
+        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    };
     let execution = t0.elapsed();
 
-    // Always tear down, then surface a run panic over a teardown error.
+    let run_result = match run_result {
+        Ok(run_result) => run_result,
+        Err(RecvTimeoutError::Timeout) => {
+            ABANDONED_RUN.store(true, std::sync::atomic::Ordering::SeqCst);
+            return Err(HintsTimeout(execution).into());
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            anyhow::bail!("hints run ended without reporting a result")
+        }
+    };
+
+    // Always tear down a finished run, then surface a run panic over a
+    // teardown error.
     let deinit_result = deinit();
     if let Err(e) = run_result {
         let msg = e
@@ -82,17 +146,40 @@ fn ensure_emits_hints(client: &dyn ExecutionClient) -> Result<()> {
     Ok(())
 }
 
+/// Fail, and remove the file, when a run of a client that claims to emit
+/// hints left nothing past the start header in `path`: a hints file that
+/// drives no run is worse than none.
+#[cfg(zisk_hints)]
+fn ensure_hints_written(client: &dyn ExecutionClient, path: &Path) -> Result<()> {
+    let len = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read the hints written to {}", path.display()))?
+        .len();
+    if len <= HINTS_START_HEADER_LEN {
+        let _ = std::fs::remove_file(path);
+        anyhow::bail!(
+            "client '{}' claims to emit hints but its run recorded none",
+            client.name()
+        );
+    }
+    Ok(())
+}
+
 /// Generate hints for one input and write them to `output_path` (batch / file sink).
+/// A run longer than `timeout` fails with [`HintsTimeout`], and its partial
+/// hints file is removed; every later run in the process then fails (see
+/// `ABANDONED_RUN`).
 #[cfg(zisk_hints)]
 pub fn generate_hints_to_file(
     stdin: &ZiskStdin,
     output_path: PathBuf,
-    client: &dyn ExecutionClient,
+    timeout: Option<Duration>,
+    client: &Arc<dyn ExecutionClient>,
 ) -> Result<(Duration, Duration)> {
-    ensure_emits_hints(client)?;
-    let (execution, total) = run_with_hints(
+    ensure_emits_hints(client.as_ref())?;
+    let result = run_with_hints(
         stdin,
         client,
+        timeout,
         || {
             // SAFETY: single-threaded setup, before the run begins.
             unsafe { std::env::set_var("ZISK_HINTS_OUTPUT", &output_path) };
@@ -103,7 +190,18 @@ pub fn generate_hints_to_file(
             ziskos::zkvm_deinit();
             Ok(())
         },
-    )?;
+    );
+    let (execution, total) = match result {
+        Ok(durations) => durations,
+        Err(e) => {
+            if e.downcast_ref::<HintsTimeout>().is_some() {
+                // Unlinked, the file keeps taking the abandoned run's writes.
+                let _ = std::fs::remove_file(&output_path);
+            }
+            return Err(e);
+        }
+    };
+    ensure_hints_written(client.as_ref(), &output_path)?;
     tracing::info!(
         "Written hints to {} (execution: {:.2?}, total: {:.2?})",
         output_path.display(),
@@ -117,7 +215,9 @@ pub fn generate_hints_to_file(
 /// sink) so a prover can consume them while they are produced; no hints file is
 /// written. `debug_file`, when `Some`, tees a copy to disk. `ready`, when `Some`,
 /// is signalled once the socket is listening — before this call blocks waiting for
-/// the prover to connect. Returns `(execution, total)` durations.
+/// the prover to connect. A run longer than `timeout` fails with
+/// [`HintsTimeout`], as do later runs in the process. Returns `(execution,
+/// total)` durations.
 #[cfg(zisk_hints)]
 pub fn generate_hints_to_socket(
     stdin: &ZiskStdin,
@@ -125,12 +225,14 @@ pub fn generate_hints_to_socket(
     debug_file: Option<PathBuf>,
     write_flush_threshold: Option<usize>,
     ready: Option<tokio::sync::oneshot::Sender<()>>,
-    client: &dyn ExecutionClient,
+    timeout: Option<Duration>,
+    client: &Arc<dyn ExecutionClient>,
 ) -> Result<(Duration, Duration)> {
-    ensure_emits_hints(client)?;
+    ensure_emits_hints(client.as_ref())?;
     run_with_hints(
         stdin,
         client,
+        timeout,
         move || ziskos::zkvm_init_socket(socket_path, debug_file, write_flush_threshold, ready),
         || ziskos::hints::close_hints(),
     )
@@ -144,7 +246,8 @@ const NO_HINTS_MSG: &str =
 pub fn generate_hints_to_file(
     _stdin: &ZiskStdin,
     _output_path: PathBuf,
-    _client: &dyn ExecutionClient,
+    _timeout: Option<Duration>,
+    _client: &Arc<dyn ExecutionClient>,
 ) -> Result<(Duration, Duration)> {
     anyhow::bail!(NO_HINTS_MSG)
 }
@@ -156,7 +259,8 @@ pub fn generate_hints_to_socket(
     _debug_file: Option<PathBuf>,
     _write_flush_threshold: Option<usize>,
     _ready: Option<tokio::sync::oneshot::Sender<()>>,
-    _client: &dyn ExecutionClient,
+    _timeout: Option<Duration>,
+    _client: &Arc<dyn ExecutionClient>,
 ) -> Result<(Duration, Duration)> {
     anyhow::bail!(NO_HINTS_MSG)
 }
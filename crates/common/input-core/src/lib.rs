@@ -1,5 +1,10 @@
 mod client;
 mod hints;
+mod retry;
 
-pub use client::{parse_header, BlockStats, ExecutionClient, RpcConfig};
-pub use hints::{generate_hints_to_file, generate_hints_to_socket};
+pub use client::{
+    parse_header, stdin_slices, BlockStats, ExecutionClient, InputFileName, InputMetadata,
+    RpcConfig,
+};
+pub use hints::{generate_hints_to_file, generate_hints_to_socket, HintsTimeout};
+pub use retry::{RetryPolicy, MIN_RATE_LIMIT};
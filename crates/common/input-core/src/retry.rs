@@ -0,0 +1,259 @@
+//! Retries, backoff and rate limiting for RPC requests, applied by every
+//! client's `from_rpc` through [`RpcConfig::request`](crate::RpcConfig::request).
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// How RPC requests that fail transiently — rate limits, timeouts, gateway
+/// errors, dropped connections — are retried. Other errors fail at once.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each one after it.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Longest a single attempt may take.
+    pub request_timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            request_timeout: Some(Duration::from_secs(120)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry `attempt` (0-based): exponential, capped, and
+    /// jittered over its upper half so concurrent requests don't retry in step.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX));
+        let capped = exponential.min(self.max_backoff);
+        capped / 2 + capped.mul_f64(jitter() / 2.0)
+    }
+}
+
+/// A uniform value in `[0, 1]`. Each `RandomState` is freshly keyed, which is
+/// random enough for spreading retries without a `rand` dependency.
+fn jitter() -> f64 {
+    RandomState::new().hash_one(()) as f64 / u64::MAX as f64
+}
+
+/// Why a failed request is worth retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Transient {
+    /// The provider asked us to slow down; backs off longer.
+    RateLimited,
+    Other,
+}
+
+/// An attempt that outlived [`RetryPolicy::request_timeout`].
+#[derive(Debug)]
+pub(crate) struct RequestTimeout(pub Duration);
+
+impl std::fmt::Display for RequestTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request timed out after {:.0?}", self.0)
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
+/// Classify a failed request by its root cause — the transport's error, not
+/// the context the client added (which may hold a block number like `429`).
+pub(crate) fn transient(error: &anyhow::Error) -> Option<Transient> {
+    if error.downcast_ref::<RequestTimeout>().is_some() {
+        return Some(Transient::Other);
+    }
+    let cause = error.root_cause().to_string().to_lowercase();
+    let has_status = |code: &str| {
+        cause
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|token| token == code)
+    };
+
+    if has_status("429")
+        || has_status("32005")
+        || [
+            "too many requests",
+            "rate limit",
+            "rate-limit",
+            "exceeded the quota",
+        ]
+        .iter()
+        .any(|p| cause.contains(p))
+    {
+        return Some(Transient::RateLimited);
+    }
+    if ["500", "502", "503", "504"]
+        .iter()
+        .any(|code| has_status(code))
+        || [
+            "timed out",
+            "timeout",
+            "bad gateway",
+            "service unavailable",
+            "connection reset",
+            "connection closed",
+            "connection refused",
+            "broken pipe",
+            "error sending request",
+            "temporarily unavailable",
+        ]
+        .iter()
+        .any(|p| cause.contains(p))
+    {
+        return Some(Transient::Other);
+    }
+    None
+}
+
+/// Slowest rate [`RpcConfig::with_rate_limit`](crate::RpcConfig::with_rate_limit)
+/// spaces requests at: one an hour. Lower rates are raised to it.
+pub const MIN_RATE_LIMIT: f64 = 1.0 / 3600.0;
+
+/// Spaces requests at least `interval` apart, across every clone of the
+/// [`RpcConfig`](crate::RpcConfig) it belongs to.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(per_second: f64) -> Self {
+        Self {
+            interval: Duration::try_from_secs_f64(1.0 / per_second.max(MIN_RATE_LIMIT))
+                .expect("bounded by MIN_RATE_LIMIT"),
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Wait for this request's slot.
+    pub(crate) async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            let slot = next.map_or(now, |next| next.max(now));
+            *next = Some(slot + self.interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn transient_errors() {
+        let cases = [
+            (
+                "HTTP error 429 with empty body",
+                Some(Transient::RateLimited),
+            ),
+            (
+                "server returned an error response: error code -32005: limit exceeded",
+                Some(Transient::RateLimited),
+            ),
+            ("Too Many Requests", Some(Transient::RateLimited)),
+            (
+                "daily request count exceeded, request rate limited",
+                Some(Transient::RateLimited),
+            ),
+            (
+                "you have exceeded the quota for this key",
+                Some(Transient::RateLimited),
+            ),
+            (
+                "HTTP error 502 with body: bad gateway",
+                Some(Transient::Other),
+            ),
+            ("HTTP error 503", Some(Transient::Other)),
+            (
+                "error sending request for url (http://localhost:8545/)",
+                Some(Transient::Other),
+            ),
+            ("connection reset by peer", Some(Transient::Other)),
+            ("operation timed out", Some(Transient::Other)),
+            (
+                "server returned an error response: error code -32602: invalid params",
+                None,
+            ),
+            ("HTTP error 404 with body: not found", None),
+            // Status codes are whole tokens, not digits inside other numbers.
+            ("header not found for block 14290", None),
+            ("HTTP error 5030", None),
+        ];
+        for (cause, expected) in cases {
+            assert_eq!(transient(&anyhow!("{cause}")), expected, "{cause}");
+        }
+    }
+
+    #[test]
+    fn transient_reads_the_root_cause_only() {
+        let permanent = anyhow!("invalid params").context("Failed to fetch block 429");
+        assert_eq!(transient(&permanent), None);
+
+        let timeout = anyhow::Error::new(RequestTimeout(Duration::from_secs(1)))
+            .context("Failed to fetch block 1");
+        assert_eq!(transient(&timeout), Some(Transient::Other));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_with_jitter() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            request_timeout: None,
+        };
+        let cases = [
+            (0, Duration::from_millis(100)),
+            (1, Duration::from_millis(200)),
+            (3, Duration::from_millis(800)),
+            (4, Duration::from_secs(1)),
+            (40, Duration::from_secs(1)),
+        ];
+        for (attempt, full) in cases {
+            for _ in 0..20 {
+                let backoff = policy.backoff(attempt);
+                assert!(
+                    backoff >= full / 2 && backoff <= full,
+                    "attempt {attempt}: {backoff:?} outside [{:?}, {full:?}]",
+                    full / 2
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rate_limiter_interval_is_bounded() {
+        assert_eq!(RateLimiter::new(4.0).interval, Duration::from_millis(250));
+        let slowest = RateLimiter::new(MIN_RATE_LIMIT).interval;
+        assert_eq!(slowest.as_secs_f64().round(), 3600.0);
+        for per_second in [1e-300, 0.0, -1.0, f64::NAN] {
+            assert_eq!(
+                RateLimiter::new(per_second).interval,
+                slowest,
+                "{per_second}"
+            );
+        }
+    }
+}
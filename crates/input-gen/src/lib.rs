@@ -1,10 +1,12 @@
 use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
 use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
 
 mod client;
 mod common;
 mod processor;
+mod progress;
 mod provider;
 
 use client::{create_client, Client};
@@ -34,6 +36,12 @@ enum ProviderCommand {
 }
 
 pub async fn run(args: InputGenArgs) -> Result<()> {
+    run_until(args, CancellationToken::new()).await
+}
+
+/// As [`run`], with `rpc --follow` stopping once `stop` is cancelled, as it
+/// does on Ctrl+C.
+pub async fn run_until(args: InputGenArgs, stop: CancellationToken) -> Result<()> {
     let client = create_client(args.client);
 
     let output = args
@@ -51,7 +59,7 @@ pub async fn run(args: InputGenArgs) -> Result<()> {
         }
         ProviderCommand::Rpc(rpc_provider) => {
             rpc_provider
-                .generate_inputs(client.as_ref(), &output)
+                .generate_inputs_until(client.as_ref(), &output, stop)
                 .await?;
         }
     }
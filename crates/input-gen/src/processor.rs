@@ -4,6 +4,9 @@ pub struct ProcessingTracker {
     client_name: String,
     success_count: usize,
     error_count: usize,
+    /// Number of items to process, when known up front; prefixes each log
+    /// line with the progress.
+    total: Option<usize>,
 }
 
 impl ProcessingTracker {
@@ -12,22 +15,43 @@ impl ProcessingTracker {
             client_name: client_name.to_string(),
             success_count: 0,
             error_count: 0,
+            total: None,
         }
     }
 
+    pub fn with_total(mut self, total: usize) -> Self {
+        self.total = Some(total);
+        self
+    }
+
     pub fn record_success(&mut self, item: &str) {
         self.success_count += 1;
-        info!("Generated {} input for: {}", self.client_name, item);
+        info!(
+            "{}Generated {} input for: {}",
+            self.progress(),
+            self.client_name,
+            item
+        );
     }
 
     pub fn record_error(&mut self, item: &str, error: &anyhow::Error) {
         self.error_count += 1;
         warn!(
-            "Failed to generate {} input for {}: {}",
-            self.client_name, item, error
+            "{}Failed to generate {} input for {}: {}",
+            self.progress(),
+            self.client_name,
+            item,
+            error
         );
     }
 
+    /// `[done/total] `, or nothing when the total isn't known.
+    fn progress(&self) -> String {
+        self.total
+            .map(|total| format!("[{}/{}] ", self.success_count + self.error_count, total))
+            .unwrap_or_default()
+    }
+
     pub fn log_summary(&self) {
         info!(
             "Completed: {} succeeded, {} failed",
@@ -0,0 +1,133 @@
+//! Which blocks of an RPC run are already generated, so a rerun after a crash
+//! (or a range job stopped and continued over days) skips them.
+//!
+//! A block counts as generated when the output folder holds an input for it,
+//! recognized by [`BlockStats::output_filename`](input::BlockStats)'s pattern,
+//! or when the progress manifest records it, so inputs moved elsewhere (e.g.
+//! picked up by a prover) aren't fetched again. Both are per chain: a block
+//! number says nothing about another chain's block.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use guest_common::chain::chain_name;
+use input::InputFileName;
+
+/// The progress manifest, kept up to date after every block of an RPC run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// The RPC's `eth_chainId`. Chains without a name share a manifest, so
+    /// this tells them apart.
+    #[serde(default)]
+    chain_id: Option<u64>,
+    /// Generated blocks, as inclusive `[start, end]` ranges.
+    completed: Vec<(u64, u64)>,
+    /// Blocks whose latest attempt failed; retried on the next run.
+    failed: Vec<u64>,
+}
+
+pub struct Progress {
+    path: PathBuf,
+    chain_id: u64,
+    completed: BTreeSet<u64>,
+    failed: BTreeSet<u64>,
+}
+
+impl Progress {
+    /// Load `client`'s progress on chain `chain_id` in `output`: its
+    /// manifest, if any, and the chain's inputs already there.
+    pub fn load(output: &Path, client: &str, chain_id: u64) -> Result<Self> {
+        let chain = chain_name(chain_id).to_lowercase();
+        let path = output.join(format!("input-gen-progress-{client}-{chain}.json"));
+        let manifest: Manifest = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            Manifest::default()
+        };
+        if let Some(recorded) = manifest.chain_id.filter(|&id| id != chain_id) {
+            anyhow::bail!(
+                "{} records blocks of chain {recorded}, but the RPC serves chain {chain_id}; \
+                 use another output folder for this chain",
+                path.display()
+            );
+        }
+
+        let mut completed: BTreeSet<u64> = manifest
+            .completed
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .collect();
+        completed.extend(existing_inputs(output, client, &chain)?);
+        Ok(Self {
+            path,
+            chain_id,
+            completed,
+            failed: manifest.failed.into_iter().collect(),
+        })
+    }
+
+    pub fn is_completed(&self, block: u64) -> bool {
+        self.completed.contains(&block)
+    }
+
+    pub fn record_success(&mut self, block: u64) -> Result<()> {
+        self.completed.insert(block);
+        self.failed.remove(&block);
+        self.save()
+    }
+
+    pub fn record_failure(&mut self, block: u64) -> Result<()> {
+        self.completed.remove(&block);
+        self.failed.insert(block);
+        self.save()
+    }
+
+    /// Write the manifest through a temporary file, so a crash mid-write
+    /// leaves the previous one intact.
+    fn save(&self) -> Result<()> {
+        let manifest = Manifest {
+            chain_id: Some(self.chain_id),
+            completed: ranges(&self.completed),
+            failed: self.failed.iter().copied().collect(),
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// Blocks with a `client` input (`.bin`) for `chain` in `output`.
+fn existing_inputs(output: &Path, client: &str, chain: &str) -> Result<Vec<u64>> {
+    let entries = fs::read_dir(output)
+        .with_context(|| format!("Failed to read output folder {}", output.display()))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bin"))
+        .filter_map(|path| InputFileName::parse(path.file_name()?.to_str()?))
+        .filter(|name| name.client == client && name.chain == chain)
+        .map(|name| name.block_number)
+        .collect())
+}
+
+fn ranges(blocks: &BTreeSet<u64>) -> Vec<(u64, u64)> {
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    for &block in blocks {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == block => *end = block,
+            _ => ranges.push((block, block)),
+        }
+    }
+    ranges
+}
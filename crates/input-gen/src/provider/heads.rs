@@ -0,0 +1,172 @@
+//! What wakes follow mode to look for new blocks: a poll timer
+//! (`--poll-interval`), or a `newHeads` subscription over WebSocket with
+//! `--ws-url`.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::{stream::BoxStream, StreamExt};
+use tracing::{debug, info, warn};
+
+use alloy_provider::{Provider, ProviderBuilder, WsConnect};
+
+/// A subscription silent for this long is taken for dead and reopened.
+const STALE_AFTER: Duration = Duration::from_secs(120);
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+pub enum Heads {
+    Poll(Duration),
+    Subscribe {
+        url: String,
+        /// The subscription's block numbers; `None` while disconnected.
+        live: Option<BoxStream<'static, u64>>,
+        reconnect_delay: Duration,
+    },
+}
+
+impl Heads {
+    pub fn new(ws_url: Option<String>, poll_interval: Duration) -> Self {
+        match ws_url {
+            Some(url) => Self::Subscribe {
+                url,
+                live: None,
+                reconnect_delay: INITIAL_RECONNECT_DELAY,
+            },
+            None => Self::Poll(poll_interval),
+        }
+    }
+
+    /// Wait until there may be new blocks. A subscription that drops is
+    /// reopened with backoff; this returns after each attempt, so the caller
+    /// backfills the blocks missed meanwhile over HTTP.
+    pub async fn next(&mut self) {
+        let (url, live, reconnect_delay) = match self {
+            Self::Poll(interval) => {
+                tokio::time::sleep(*interval).await;
+                return;
+            }
+            Self::Subscribe {
+                url,
+                live,
+                reconnect_delay,
+            } => (url, live, reconnect_delay),
+        };
+
+        if live.is_none() {
+            match subscribe(url).await {
+                Ok(subscription) => {
+                    info!("Subscribed to newHeads at {url}");
+                    *live = Some(subscription);
+                    *reconnect_delay = INITIAL_RECONNECT_DELAY;
+                }
+                Err(e) => {
+                    warn!("{e:#}; retrying in {reconnect_delay:?}");
+                    tokio::time::sleep(*reconnect_delay).await;
+                    *reconnect_delay = (*reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+            return;
+        }
+
+        let stream = live.as_mut().expect("checked above");
+        let next = tokio::time::timeout(STALE_AFTER, stream.next()).await;
+        match next {
+            Ok(Some(number)) => debug!("New head: block #{number}"),
+            Ok(None) => {
+                warn!("newHeads subscription closed; reconnecting");
+                *live = None;
+            }
+            Err(_) => {
+                warn!("No new head for {STALE_AFTER:?}; reconnecting");
+                *live = None;
+            }
+        }
+    }
+}
+
+async fn subscribe(url: &str) -> Result<BoxStream<'static, u64>> {
+    let provider = ProviderBuilder::new()
+        .connect_ws(WsConnect::new(url))
+        .await
+        .with_context(|| format!("Failed to connect to {url}"))?
+        .erased();
+    let subscription = provider
+        .subscribe_blocks()
+        .await
+        .with_context(|| format!("Failed to subscribe to newHeads at {url}"))?;
+    // The stream owns the provider, which keeps the connection open.
+    let numbers = subscription
+        .into_stream()
+        .map(move |header| {
+            let _connection = &provider;
+            header.number
+        })
+        .boxed();
+    Ok(numbers)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+
+    use super::*;
+
+    fn subscribed(live: Option<BoxStream<'static, u64>>) -> Heads {
+        Heads::Subscribe {
+            // Nothing listens on port 1, so connecting fails at once.
+            url: "ws://127.0.0.1:1".to_string(),
+            live,
+            reconnect_delay: INITIAL_RECONNECT_DELAY,
+        }
+    }
+
+    fn state(heads: &Heads) -> (bool, Duration) {
+        match heads {
+            Heads::Subscribe {
+                live,
+                reconnect_delay,
+                ..
+            } => (live.is_some(), *reconnect_delay),
+            Heads::Poll(_) => unreachable!("subscribed"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_connections_back_off_up_to_the_cap() {
+        let mut heads = subscribed(None);
+        let mut delays = Vec::new();
+        for _ in 0..7 {
+            heads.next().await;
+            let (live, delay) = state(&heads);
+            assert!(!live);
+            delays.push(delay.as_secs());
+        }
+        assert_eq!(delays, [2, 4, 8, 16, 30, 30, 30]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn new_head_keeps_the_subscription() {
+        let heads_stream = stream::iter([100]).chain(stream::pending()).boxed();
+        let mut heads = subscribed(Some(heads_stream));
+        heads.next().await;
+        assert_eq!(state(&heads), (true, INITIAL_RECONNECT_DELAY));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn closed_subscription_is_dropped() {
+        let mut heads = subscribed(Some(stream::empty().boxed()));
+        heads.next().await;
+        assert!(!state(&heads).0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn silent_subscription_is_dropped_once_stale() {
+        let mut heads = subscribed(Some(stream::pending().boxed()));
+        let start = tokio::time::Instant::now();
+        heads.next().await;
+        assert!(start.elapsed() >= STALE_AFTER);
+        assert!(!state(&heads).0);
+    }
+}
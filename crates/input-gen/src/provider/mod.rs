@@ -1,4 +1,6 @@
 pub mod eest;
+mod heads;
+mod reorg;
 pub mod rpc;
 
 use crate::client::InputGenClient;
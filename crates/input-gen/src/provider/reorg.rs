@@ -0,0 +1,87 @@
+//! Follow mode's record of the blocks it emitted inputs for, so a reorg that
+//! replaces some of them is noticed and their inputs set aside.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy_primitives::B256;
+use tracing::warn;
+
+/// Where inputs for reorged-out blocks are moved, under the output folder.
+pub const QUARANTINE_DIR: &str = "reorged";
+
+/// How many of the latest emitted blocks are remembered. A reorg deeper than
+/// this is still noticed, but only its most recent blocks are set aside.
+const WINDOW: usize = 256;
+
+struct Emitted {
+    hash: B256,
+    file: PathBuf,
+}
+
+#[derive(Default)]
+pub struct EmittedBlocks {
+    blocks: BTreeMap<u64, Emitted>,
+}
+
+impl EmittedBlocks {
+    pub fn record(&mut self, number: u64, hash: B256, file: PathBuf) {
+        self.blocks.insert(number, Emitted { hash, file });
+        while self.blocks.len() > WINDOW {
+            self.blocks.pop_first();
+        }
+    }
+
+    pub fn hash(&self, number: u64) -> Option<B256> {
+        self.blocks.get(&number).map(|emitted| emitted.hash)
+    }
+
+    /// The emitted blocks, newest first.
+    pub fn newest_first(&self) -> impl Iterator<Item = (u64, B256)> + '_ {
+        self.blocks
+            .iter()
+            .rev()
+            .map(|(&number, emitted)| (number, emitted.hash))
+    }
+
+    /// Forget the blocks from `fork` on and move their inputs to
+    /// [`QUARANTINE_DIR`], tagged with the replaced hash so a block reorged
+    /// twice keeps both. An input that can't be moved is left in place and
+    /// logged, so follow mode carries on. Returns how many were moved.
+    pub fn quarantine_from(&mut self, fork: u64, output: &Path) -> usize {
+        let reorged = self.blocks.split_off(&fork);
+        if reorged.is_empty() {
+            return 0;
+        }
+        let quarantine = output.join(QUARANTINE_DIR);
+        if let Err(e) = fs::create_dir_all(&quarantine) {
+            warn!("Failed to create {}: {e}", quarantine.display());
+            return 0;
+        }
+        let mut moved = 0;
+        for emitted in reorged.values() {
+            let stem = emitted
+                .file
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy();
+            let to = quarantine.join(format!("{stem}_{:.8}.bin", hex(&emitted.hash)));
+            match fs::rename(&emitted.file, &to) {
+                Ok(()) => moved += 1,
+                Err(e) => warn!(
+                    "Failed to move {} to {}: {e}",
+                    emitted.file.display(),
+                    to.display()
+                ),
+            }
+        }
+        moved
+    }
+}
+
+fn hex(hash: &B256) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
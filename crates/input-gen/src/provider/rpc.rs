@@ -1,23 +1,39 @@
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use anyhow::{Context, Result};
 use clap::Args;
+use futures::{stream, StreamExt};
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{info, warn};
 
+use alloy_eips::BlockNumberOrTag;
+use alloy_primitives::B256;
 use alloy_provider::{Provider, ProviderBuilder};
 
-use input::{parse_header, RpcConfig};
+use input::{parse_header, RetryPolicy, RpcConfig, MIN_RATE_LIMIT};
 
-use super::{InputProvider, ProviderKind};
-use crate::{client::InputGenClient, processor::ProcessingTracker};
+use super::{
+    heads::Heads,
+    reorg::{EmittedBlocks, QUARANTINE_DIR},
+    InputProvider, ProviderKind,
+};
+use crate::{client::InputGenClient, processor::ProcessingTracker, progress::Progress};
 
 #[derive(Debug, Clone, Args)]
 pub struct RpcProvider {
-    /// RPC URL to use
-    #[arg(short = 'u', long)]
+    /// RPC URL to use (HTTP)
+    #[arg(short = 'u', long, value_parser = parse_http_url)]
     rpc_url: String,
 
+    /// With --follow, a WebSocket RPC (ws:// or wss://) to subscribe to new
+    /// heads on instead of polling every --poll-interval. Blocks are still
+    /// fetched over --rpc-url.
+    #[arg(long, value_name = "URL", requires = "follow", value_parser = parse_ws_url)]
+    ws_url: Option<String>,
+
     /// Optional RPC headers (format: "Key:Value", repeatable). Only honored by
     /// clients that support custom HTTP headers (reth).
     #[arg(short = 'H', long, value_parser = parse_header)]
@@ -38,6 +54,48 @@ pub struct RpcProvider {
     /// Listen for new blocks
     #[arg(short = 'f', long, default_value_t = false, group = "block_selection")]
     follow: bool,
+
+    /// With --follow, only emit blocks at least N blocks below the head
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "follow")]
+    confirmations: u64,
+
+    /// With --follow, only emit finalized blocks
+    #[arg(long, requires = "follow", conflicts_with = "confirmations")]
+    finalized: bool,
+
+    /// With --follow, milliseconds between polls for new blocks (without
+    /// --ws-url)
+    #[arg(long, value_name = "MS", default_value_t = 6000, requires = "follow")]
+    poll_interval: u64,
+
+    /// Number of blocks to fetch at once (not with --follow). Blocks are still
+    /// reported in order.
+    #[arg(long, default_value_t = 1, conflicts_with = "follow")]
+    concurrency: usize,
+
+    /// Regenerate blocks already generated in the output folder, instead of
+    /// skipping them (not with --follow)
+    #[arg(long, conflicts_with = "follow")]
+    force: bool,
+
+    /// Times to retry a request that failed transiently (rate limit, timeout,
+    /// gateway error, dropped connection) before failing the block
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Delay before the first retry, in milliseconds; doubled (with jitter) on
+    /// each retry after it, up to 30s
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    retry_backoff: u64,
+
+    /// Longest a single request may take, in seconds (0 = no limit)
+    #[arg(long, value_name = "SECS", default_value_t = 120)]
+    request_timeout: u64,
+
+    /// Most requests per second to send, across all blocks in flight (at
+    /// least one an hour)
+    #[arg(long, value_name = "N", value_parser = parse_max_rps)]
+    max_rps: Option<f64>,
 }
 
 #[async_trait::async_trait]
@@ -47,6 +105,20 @@ impl InputProvider for RpcProvider {
     }
 
     async fn generate_inputs(&self, client: &dyn InputGenClient, output: &Path) -> Result<()> {
+        self.generate_inputs_until(client, output, CancellationToken::new())
+            .await
+    }
+}
+
+impl RpcProvider {
+    /// As [`InputProvider::generate_inputs`], with `--follow` stopping once
+    /// `stop` is cancelled, as Ctrl+C does.
+    pub async fn generate_inputs_until(
+        &self,
+        client: &dyn InputGenClient,
+        output: &Path,
+        stop: CancellationToken,
+    ) -> Result<()> {
         if !client.supports_provider(self.kind()) {
             anyhow::bail!("{} doesn't support RPC provider", client.display_name());
         }
@@ -62,21 +134,37 @@ impl InputProvider for RpcProvider {
 
         // If follow is enabled, continuously listen for new blocks.
         if self.follow {
-            return self.follow_new_blocks(output, client).await;
+            return self.follow_new_blocks(output, client, stop).await;
         }
 
         // Otherwise, process specified blocks.
         self.process_batch(output, client).await
     }
-}
 
-impl RpcProvider {
+    /// One config per run: clients keep their connection to the endpoint in
+    /// it, so every block of the run reuses it, and the rate limit holds
+    /// across them.
     fn rpc_config(&self) -> RpcConfig {
-        RpcConfig::new(self.rpc_url.clone()).with_headers(self.rpc_headers.clone())
+        let retry = RetryPolicy {
+            max_retries: self.retries,
+            initial_backoff: Duration::from_millis(self.retry_backoff),
+            request_timeout: (self.request_timeout > 0)
+                .then(|| Duration::from_secs(self.request_timeout)),
+            ..Default::default()
+        };
+        let config = RpcConfig::new(self.rpc_url.clone())
+            .with_headers(self.rpc_headers.clone())
+            .with_retry(retry);
+        match self.max_rps {
+            Some(max_rps) => config.with_rate_limit(max_rps),
+            None => config,
+        }
     }
 
     async fn process_batch(&self, output: &Path, client: &dyn InputGenClient) -> Result<()> {
-        let block_numbers: Vec<u64> = if let Some(block_num) = self.block {
+        let config = self.rpc_config();
+        let provider = self.connect_provider().await?;
+        let mut block_numbers: Vec<u64> = if let Some(block_num) = self.block {
             vec![block_num]
         } else if let Some(range) = &self.range_of_blocks {
             if range.len() != 2 {
@@ -93,25 +181,55 @@ impl RpcProvider {
                 info!("No blocks to process (last_n_blocks = 0)");
                 return Ok(());
             }
-            let provider = self.connect_provider().await?;
-            let latest = fetch_latest_block_number(&provider).await?;
+            let latest = fetch_latest_block_number(&config, &provider).await?;
             let start = latest.saturating_sub(n as u64 - 1);
             (start..=latest).collect()
         };
 
+        let chain_id = fetch_chain_id(&config, &provider).await?;
+        let mut progress = Progress::load(output, client.name(), chain_id)?;
+        if !self.force {
+            let requested = block_numbers.len();
+            block_numbers.retain(|&block_num| !progress.is_completed(block_num));
+            let skipped = requested - block_numbers.len();
+            if skipped > 0 {
+                info!("Skipping {skipped} block(s) already generated (--force to regenerate)");
+            }
+            if block_numbers.is_empty() {
+                return Ok(());
+            }
+        }
+
         info!(
             "Processing {} block(s): {:?}",
             block_numbers.len(),
             block_numbers
         );
 
-        let config = self.rpc_config();
-        let mut tracker = ProcessingTracker::new(client.display_name());
-        for block_num in block_numbers {
+        let mut tracker =
+            ProcessingTracker::new(client.display_name()).with_total(block_numbers.len());
+        // `buffered` runs up to `concurrency` blocks at once but yields them in
+        // order, so progress reads block by block.
+        let mut blocks = stream::iter(block_numbers)
+            .map(|block_num| {
+                let config = &config;
+                async move {
+                    let result = self.process_block(config, block_num, output, client).await;
+                    (block_num, result)
+                }
+            })
+            .buffered(self.concurrency.max(1));
+        while let Some((block_num, result)) = blocks.next().await {
             let name = format!("Block #{}", block_num);
-            match self.process_block(&config, block_num, output, client).await {
-                Ok(_) => tracker.record_success(&name),
-                Err(e) => tracker.record_error(&name, &e),
+            match result {
+                Ok(_) => {
+                    tracker.record_success(&name);
+                    progress.record_success(block_num)?;
+                }
+                Err(e) => {
+                    tracker.record_error(&name, &e);
+                    progress.record_failure(block_num)?;
+                }
             }
         }
         tracker.log_summary();
@@ -121,10 +239,14 @@ impl RpcProvider {
         Ok(())
     }
 
-    async fn follow_new_blocks(&self, output: &Path, client: &dyn InputGenClient) -> Result<()> {
+    async fn follow_new_blocks(
+        &self,
+        output: &Path,
+        client: &dyn InputGenClient,
+        stop: CancellationToken,
+    ) -> Result<()> {
         info!("Following new blocks (press Ctrl+C to stop)...");
 
-        let stop = CancellationToken::new();
         let stop_clone = stop.clone();
         tokio::spawn(async move {
             tokio::signal::ctrl_c()
@@ -137,30 +259,93 @@ impl RpcProvider {
         let config = self.rpc_config();
         let provider = self.connect_provider().await?;
         let mut tracker = ProcessingTracker::new(client.display_name());
-        let mut next_block_num = fetch_latest_block_number(&provider).await?;
+        let mut emitted = EmittedBlocks::default();
+        let mut next_block_num = self.safe_head(&config, &provider).await?;
+        let mut heads = Heads::new(
+            self.ws_url.clone(),
+            Duration::from_millis(self.poll_interval),
+        );
 
         loop {
             if stop.is_cancelled() {
                 break;
             }
 
-            let latest = fetch_latest_block_number(&provider).await?;
-            for block_num in next_block_num..=latest {
-                if stop.is_cancelled() {
-                    break;
+            // An RPC error that outlives the retries skips to the next head
+            // rather than ending the session.
+            'poll: {
+                // Inputs for blocks that left the canonical chain are set
+                // aside, and the blocks that replaced them generated in their
+                // place.
+                let fork = match find_fork(&config, &provider, &emitted).await {
+                    Ok(fork) => fork,
+                    Err(e) => {
+                        warn!("Reorg check failed, retrying at the next head: {e:#}");
+                        break 'poll;
+                    }
+                };
+                if let Some(fork) = fork {
+                    let moved = emitted.quarantine_from(fork, output);
+                    warn!(
+                        "Reorg from block #{fork}: moved {moved} input(s) to {}, regenerating",
+                        output.join(QUARANTINE_DIR).display()
+                    );
+                    next_block_num = next_block_num.min(fork);
                 }
-                let name = format!("Block #{}", block_num);
-                match self.process_block(&config, block_num, output, client).await {
-                    Ok(_) => tracker.record_success(&name),
-                    Err(e) => tracker.record_error(&name, &e),
+
+                // Everything from the last emitted block up is generated,
+                // which also backfills blocks missed while a subscription was
+                // down. Polling is retried like the block fetches, so a long
+                // session outlives a provider hiccup.
+                let head = match self.safe_head(&config, &provider).await {
+                    Ok(head) => head,
+                    Err(e) => {
+                        warn!("Failed to read the head, retrying at the next one: {e:#}");
+                        break 'poll;
+                    }
+                };
+                for block_num in next_block_num..=head {
+                    if stop.is_cancelled() {
+                        break;
+                    }
+                    let hashes = match fetch_block_hashes(&config, &provider, block_num).await {
+                        Ok(hashes) => hashes,
+                        Err(e) => {
+                            warn!(
+                                "Failed to fetch block #{block_num}'s hash, retrying at the next head: {e:#}"
+                            );
+                            break 'poll;
+                        }
+                    };
+                    let Some((hash, parent_hash)) = hashes else {
+                        // The head moved back under us; the next poll finds
+                        // the fork.
+                        break;
+                    };
+                    if emitted
+                        .hash(block_num.saturating_sub(1))
+                        .is_some_and(|parent| parent != parent_hash)
+                    {
+                        // The chain moved under us; the next poll finds the
+                        // fork.
+                        break;
+                    }
+
+                    let name = format!("Block #{}", block_num);
+                    match self.process_block(&config, block_num, output, client).await {
+                        Ok(file) => {
+                            emitted.record(block_num, hash, file);
+                            tracker.record_success(&name);
+                        }
+                        Err(e) => tracker.record_error(&name, &e),
+                    }
+                    next_block_num = block_num + 1;
                 }
             }
 
-            next_block_num = latest + 1;
-
             tokio::select! {
                 _ = stop.cancelled() => break,
-                _ = tokio::time::sleep(std::time::Duration::from_secs(6)) => {}
+                _ = heads.next() => {}
             }
         }
 
@@ -174,14 +359,40 @@ impl RpcProvider {
         block_num: u64,
         output: &Path,
         client: &dyn InputGenClient,
-    ) -> Result<()> {
+    ) -> Result<PathBuf> {
         let (stdin, stats) = client.from_rpc(config, block_num).await?;
-        stdin.save(output.join(stats.output_filename(client.name())))?;
-        Ok(())
+        let file = output.join(stats.output_filename(client.name()));
+        // Written aside and renamed, so an interrupted run never leaves a
+        // truncated input that a rerun would take as generated.
+        let partial = file.with_extension("bin.partial");
+        stdin.save(&partial)?;
+        std::fs::rename(&partial, &file)
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+        Ok(file)
     }
 
-    /// Used only for `eth_blockNumber` polling — headers are intentionally
-    /// not threaded through; the per-block work goes through `from_rpc`.
+    /// The newest block follow mode may emit: the finalized block with
+    /// `--finalized`, else `--confirmations` below the head.
+    async fn safe_head<P: Provider>(&self, config: &RpcConfig, provider: &P) -> Result<u64> {
+        if self.finalized {
+            return config
+                .request("eth_getBlockByNumber", || async {
+                    let block = provider
+                        .get_block_by_number(BlockNumberOrTag::Finalized)
+                        .await
+                        .context("Failed to fetch the finalized block")?
+                        .context("The RPC has no finalized block")?;
+                    Ok(block.header.number)
+                })
+                .await;
+        }
+        let latest = fetch_latest_block_number(config, provider).await?;
+        Ok(latest.saturating_sub(self.confirmations))
+    }
+
+    /// Used only for chain and head lookups (`eth_chainId`, `eth_blockNumber`)
+    /// — headers are intentionally not threaded through; the per-block work
+    /// goes through `from_rpc`.
     async fn connect_provider(&self) -> Result<impl Provider> {
         ProviderBuilder::new()
             .connect(&self.rpc_url)
@@ -190,9 +401,87 @@ impl RpcProvider {
     }
 }
 
-async fn fetch_latest_block_number<P: Provider>(provider: &P) -> Result<u64> {
-    provider
-        .get_block_number()
+/// The hash and parent hash of canonical block `number`, or `None` when the
+/// chain doesn't reach it (e.g. after a reorg to a shorter chain).
+async fn fetch_block_hashes<P: Provider>(
+    config: &RpcConfig,
+    provider: &P,
+    number: u64,
+) -> Result<Option<(B256, B256)>> {
+    config
+        .request("eth_getBlockByNumber", || async {
+            let block = provider
+                .get_block_by_number(number.into())
+                .await
+                .with_context(|| format!("Failed to fetch block #{number}"))?;
+            Ok(block.map(|block| (block.header.hash, block.header.parent_hash)))
+        })
         .await
-        .context("Failed to fetch latest block number")
+}
+
+/// The first emitted block no longer on the canonical chain, if any; one the
+/// chain no longer reaches counts as replaced. Checks the newest first, so a
+/// poll without a reorg costs one request.
+async fn find_fork<P: Provider>(
+    config: &RpcConfig,
+    provider: &P,
+    emitted: &EmittedBlocks,
+) -> Result<Option<u64>> {
+    let mut fork = None;
+    for (number, hash) in emitted.newest_first() {
+        let canonical = fetch_block_hashes(config, provider, number).await?;
+        if canonical.is_some_and(|(canonical, _)| canonical == hash) {
+            break;
+        }
+        fork = Some(number);
+    }
+    if fork.is_some() && fork == emitted.newest_first().last().map(|(number, _)| number) {
+        warn!("Reorg reaches past the oldest tracked block; older inputs may be stale");
+    }
+    Ok(fork)
+}
+
+async fn fetch_chain_id<P: Provider>(config: &RpcConfig, provider: &P) -> Result<u64> {
+    config
+        .request("eth_chainId", || async {
+            provider
+                .get_chain_id()
+                .await
+                .context("Failed to fetch the chain ID")
+        })
+        .await
+}
+
+async fn fetch_latest_block_number<P: Provider>(config: &RpcConfig, provider: &P) -> Result<u64> {
+    config
+        .request("eth_blockNumber", || async {
+            provider
+                .get_block_number()
+                .await
+                .context("Failed to fetch latest block number")
+        })
+        .await
+}
+
+fn parse_http_url(s: &str) -> Result<String, String> {
+    if s.starts_with("ws://") || s.starts_with("wss://") {
+        return Err("blocks are fetched over HTTP; pass a WebSocket RPC as --ws-url".to_string());
+    }
+    Ok(s.to_string())
+}
+
+fn parse_ws_url(s: &str) -> Result<String, String> {
+    if !(s.starts_with("ws://") || s.starts_with("wss://")) {
+        return Err(format!("expected a ws:// or wss:// URL, got {s:?}"));
+    }
+    Ok(s.to_string())
+}
+
+fn parse_max_rps(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= MIN_RATE_LIMIT => Ok(n),
+        _ => Err(format!(
+            "expected at least one request an hour ({MIN_RATE_LIMIT:.6}), got {s:?}"
+        )),
+    }
 }
@@ -0,0 +1,464 @@
+//! Drives the RPC provider against a mock JSON-RPC endpoint serving empty
+//! mainnet blocks.
+
+use std::{
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use clap::Parser;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use tokio_util::sync::CancellationToken;
+
+use input_gen::{run, run_until, InputGenArgs};
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    args: InputGenArgs,
+}
+
+/// Requests the mock has answered, by method.
+#[derive(Default)]
+struct Calls {
+    chain_id: AtomicUsize,
+    block: AtomicUsize,
+    witness: AtomicUsize,
+    /// Witness requests still to be turned away with a 429.
+    rate_limited: AtomicUsize,
+    /// `eth_blockNumber` requests still to be turned away with a 429.
+    head_unavailable: AtomicUsize,
+    /// The chain's head; blocks above it are not found.
+    head: AtomicU64,
+    /// Blocks from this one on have the hashes of a reorged chain.
+    reorged_from: AtomicU64,
+}
+
+impl Calls {
+    /// Block hashes tell the chain apart in their first byte: `aa` before a
+    /// reorg, `bb` after it.
+    fn block_hash(&self, number: u64) -> String {
+        let chain = if number >= self.reorged_from.load(Ordering::Relaxed) {
+            0xbb
+        } else {
+            0xaa
+        };
+        format!("0x{chain:02x}{number:062x}")
+    }
+}
+
+/// A mock that rate-limits the first `rate_limited` witness requests.
+async fn start_mock(rate_limited: usize) -> (SocketAddr, Arc<Calls>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let calls = Arc::new(Calls {
+        rate_limited: AtomicUsize::new(rate_limited),
+        head: AtomicU64::new(103),
+        reorged_from: AtomicU64::new(u64::MAX),
+        ..Default::default()
+    });
+    let served = Arc::clone(&calls);
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.expect("accept");
+            tokio::spawn(serve(stream, Arc::clone(&served)));
+        }
+    });
+    (addr, calls)
+}
+
+/// Answer HTTP/1.1 JSON-RPC requests on `stream` until the client hangs up.
+async fn serve(stream: TcpStream, calls: Arc<Calls>) {
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().expect("content length");
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        stream.read_exact(&mut body).await.expect("body");
+        let request: Value = serde_json::from_slice(&body).expect("JSON-RPC request");
+
+        let (status, response) = match respond(&request, &calls) {
+            Some(result) => {
+                let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+                ("200 OK", response.to_string())
+            }
+            None => ("429 Too Many Requests", "rate limited".to_string()),
+        };
+        let http = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            response.len(),
+            response
+        );
+        stream
+            .get_mut()
+            .write_all(http.as_bytes())
+            .await
+            .expect("response");
+    }
+}
+
+/// The result for `request`, or `None` to rate-limit it.
+fn respond(request: &Value, calls: &Calls) -> Option<Value> {
+    let zero = format!("0x{}", "0".repeat(64));
+    let result = match request["method"].as_str().expect("method") {
+        "eth_chainId" => {
+            calls.chain_id.fetch_add(1, Ordering::Relaxed);
+            json!("0x1")
+        }
+        "eth_blockNumber" => {
+            let throttled = calls
+                .head_unavailable
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok();
+            if throttled {
+                return None;
+            }
+            json!(format!("0x{:x}", calls.head.load(Ordering::Relaxed)))
+        }
+        "eth_getBlockByNumber" => {
+            calls.block.fetch_add(1, Ordering::Relaxed);
+            let number = request["params"][0].as_str().expect("block number");
+            let number = u64::from_str_radix(number.trim_start_matches("0x"), 16).expect("hex");
+            if number > calls.head.load(Ordering::Relaxed) {
+                return Some(Value::Null);
+            }
+            json!({
+                "hash": calls.block_hash(number),
+                "parentHash": calls.block_hash(number.saturating_sub(1)),
+                "sha3Uncles": zero,
+                "miner": format!("0x{}", "0".repeat(40)),
+                "stateRoot": zero,
+                "transactionsRoot": zero,
+                "receiptsRoot": zero,
+                "logsBloom": format!("0x{}", "0".repeat(512)),
+                "difficulty": "0x0",
+                "number": request["params"][0],
+                "gasLimit": "0x1c9c380",
+                "gasUsed": "0x0",
+                "timestamp": "0x0",
+                "extraData": "0x",
+                "mixHash": zero,
+                "nonce": "0x0000000000000000",
+                "baseFeePerGas": "0x7",
+                "uncles": [],
+                "transactions": [],
+            })
+        }
+        "debug_executionWitness" => {
+            let throttled = calls
+                .rate_limited
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok();
+            if throttled {
+                return None;
+            }
+            calls.witness.fetch_add(1, Ordering::Relaxed);
+            json!({ "state": [], "codes": [], "keys": [], "headers": [] })
+        }
+        method => panic!("unexpected method {method}"),
+    };
+    Some(result)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_range_connects_once() {
+    let (addr, calls) = start_mock(0).await;
+    let output = tempfile::tempdir().expect("tempdir");
+
+    let url = format!("http://{addr}");
+    let cli = Cli::parse_from([
+        "input-gen",
+        "-o",
+        output.path().to_str().expect("utf-8 path"),
+        "rpc",
+        "-u",
+        &url,
+        "-r",
+        "100",
+        "103",
+        "--concurrency",
+        "3",
+    ]);
+    run(cli.args).await.expect("input generation");
+
+    for block in 100..=103 {
+        let file = output
+            .path()
+            .join(format!("mainnet_{block}_0_0_zec_reth.bin"));
+        assert!(file.exists(), "missing {}", file.display());
+    }
+    // One connection per run: the chain is looked up once for the progress
+    // manifest and once by the client's connection, not per block.
+    assert_eq!(calls.chain_id.load(Ordering::Relaxed), 2);
+    assert_eq!(calls.block.load(Ordering::Relaxed), 4);
+    assert_eq!(calls.witness.load(Ordering::Relaxed), 4);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rate_limited_witness_is_retried() {
+    let (addr, calls) = start_mock(2).await;
+    let output = tempfile::tempdir().expect("tempdir");
+
+    let url = format!("http://{addr}");
+    let cli = Cli::parse_from([
+        "input-gen",
+        "-o",
+        output.path().to_str().expect("utf-8 path"),
+        "rpc",
+        "-u",
+        &url,
+        "-b",
+        "100",
+        "--retry-backoff",
+        "10",
+    ]);
+    run(cli.args).await.expect("input generation");
+
+    assert!(output.path().join("mainnet_100_0_0_zec_reth.bin").exists());
+    // Both 429s were retried rather than failing the block.
+    assert_eq!(calls.rate_limited.load(Ordering::Relaxed), 0);
+    assert_eq!(calls.witness.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rerun_skips_generated_blocks() {
+    let (addr, calls) = start_mock(0).await;
+    let output = tempfile::tempdir().expect("tempdir");
+
+    let url = format!("http://{addr}");
+    let range = |start: &str, end: &str, force: bool| {
+        let mut args = vec![
+            "input-gen",
+            "-o",
+            output.path().to_str().expect("utf-8 path"),
+            "rpc",
+            "-u",
+            &url,
+            "-r",
+        ];
+        args.extend([start, end]);
+        if force {
+            args.push("--force");
+        }
+        Cli::parse_from(args).args
+    };
+
+    run(range("100", "101", false)).await.expect("first run");
+    assert_eq!(calls.block.load(Ordering::Relaxed), 2);
+
+    // An input moved out of the folder still counts: the manifest has it.
+    let moved = output.path().join("mainnet_100_0_0_zec_reth.bin");
+    std::fs::remove_file(&moved).expect("remove input");
+
+    // Only the new block of an overlapping range is fetched.
+    run(range("100", "102", false)).await.expect("second run");
+    assert_eq!(calls.block.load(Ordering::Relaxed), 3);
+    assert!(!moved.exists());
+
+    run(range("100", "102", true)).await.expect("forced run");
+    assert_eq!(calls.block.load(Ordering::Relaxed), 6);
+    assert!(moved.exists());
+
+    let manifest =
+        std::fs::read_to_string(output.path().join("input-gen-progress-reth-mainnet.json"))
+            .expect("progress manifest");
+    let manifest: Value = serde_json::from_str(&manifest).expect("manifest JSON");
+    assert_eq!(manifest["chain_id"], json!(1));
+    assert_eq!(manifest["completed"], json!([[100, 102]]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn progress_is_kept_per_chain() {
+    let (addr, calls) = start_mock(0).await;
+    let output = tempfile::tempdir().expect("tempdir");
+
+    let url = format!("http://{addr}");
+    let block = |block: &str| {
+        Cli::parse_from([
+            "input-gen",
+            "-o",
+            output.path().to_str().expect("utf-8 path"),
+            "rpc",
+            "-u",
+            &url,
+            "-b",
+            block,
+        ])
+        .args
+    };
+
+    // Another chain's input for the block doesn't count as generated.
+    std::fs::write(output.path().join("sepolia_100_0_0_zec_reth.bin"), b"").expect("sepolia input");
+    run(block("100")).await.expect("mainnet run");
+    assert_eq!(calls.block.load(Ordering::Relaxed), 1);
+
+    // A manifest recording another chain is refused rather than mixed in.
+    let manifest = output.path().join("input-gen-progress-reth-mainnet.json");
+    std::fs::write(
+        &manifest,
+        r#"{"chain_id": 5, "completed": [], "failed": []}"#,
+    )
+    .expect("manifest");
+    let error = run(block("101")).await.expect_err("chain mismatch");
+    assert!(
+        format!("{error:#}").contains("records blocks of chain 5"),
+        "{error:#}"
+    );
+    assert_eq!(calls.block.load(Ordering::Relaxed), 1);
+}
+
+/// Wait for follow mode to write (or move) `path`.
+async fn wait_for(path: &Path) {
+    tokio::time::timeout(Duration::from_secs(30), async {
+        while !path.exists() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{} never appeared", path.display()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn follow_sets_reorged_inputs_aside() {
+    let (addr, calls) = start_mock(0).await;
+    calls.head.store(102, Ordering::Relaxed);
+    let output = tempfile::tempdir().expect("tempdir");
+
+    let url = format!("http://{addr}");
+    let cli = Cli::parse_from([
+        "input-gen",
+        "-o",
+        output.path().to_str().expect("utf-8 path"),
+        "rpc",
+        "-u",
+        &url,
+        "--follow",
+        "--poll-interval",
+        "20",
+    ]);
+    let stop = CancellationToken::new();
+    let input = |block: u64| {
+        output
+            .path()
+            .join(format!("mainnet_{block}_0_0_zec_reth.bin"))
+    };
+    let chain = async {
+        wait_for(&input(102)).await;
+        calls.head.store(103, Ordering::Relaxed);
+        wait_for(&input(103)).await;
+
+        // A reorg to a shorter chain: block 103 is gone and 102 replaced.
+        calls.head.store(102, Ordering::Relaxed);
+        calls.reorged_from.store(102, Ordering::Relaxed);
+        let reorged = output.path().join("reorged");
+        wait_for(&reorged.join("mainnet_103_0_0_zec_reth_aa000000.bin")).await;
+        wait_for(&reorged.join("mainnet_102_0_0_zec_reth_aa000000.bin")).await;
+        // The replacement of 102 is generated in its place.
+        wait_for(&input(102)).await;
+        stop.cancel();
+    };
+    let (follow, ()) = tokio::join!(run_until(cli.args, stop.clone()), chain);
+    follow.expect("follow mode ends cleanly");
+    assert!(!input(103).exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn follow_backfills_every_block_since_the_last_emitted() {
+    let (addr, calls) = start_mock(0).await;
+    calls.head.store(102, Ordering::Relaxed);
+    let output = tempfile::tempdir().expect("tempdir");
+
+    let url = format!("http://{addr}");
+    let cli = Cli::parse_from([
+        "input-gen",
+        "-o",
+        output.path().to_str().expect("utf-8 path"),
+        "rpc",
+        "-u",
+        &url,
+        "--follow",
+        "--poll-interval",
+        "20",
+    ]);
+    let stop = CancellationToken::new();
+    let input = |block: u64| {
+        output
+            .path()
+            .join(format!("mainnet_{block}_0_0_zec_reth.bin"))
+    };
+    let chain = async {
+        wait_for(&input(102)).await;
+        // The head jumps ahead, as after a dropped subscription.
+        calls.head.store(105, Ordering::Relaxed);
+        for block in 103..=105 {
+            wait_for(&input(block)).await;
+        }
+        stop.cancel();
+    };
+    let (follow, ()) = tokio::join!(run_until(cli.args, stop.clone()), chain);
+    follow.expect("follow mode ends cleanly");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn follow_outlives_failed_polls() {
+    let (addr, calls) = start_mock(0).await;
+    calls.head.store(102, Ordering::Relaxed);
+    let output = tempfile::tempdir().expect("tempdir");
+
+    let url = format!("http://{addr}");
+    let cli = Cli::parse_from([
+        "input-gen",
+        "-o",
+        output.path().to_str().expect("utf-8 path"),
+        "rpc",
+        "-u",
+        &url,
+        "--follow",
+        "--poll-interval",
+        "20",
+        "--retries",
+        "0",
+    ]);
+    let stop = CancellationToken::new();
+    let input = |block: u64| {
+        output
+            .path()
+            .join(format!("mainnet_{block}_0_0_zec_reth.bin"))
+    };
+    let chain = async {
+        wait_for(&input(102)).await;
+        // Without retries, each of these polls fails outright.
+        calls.head_unavailable.store(3, Ordering::Relaxed);
+        calls.head.store(104, Ordering::Relaxed);
+        wait_for(&input(103)).await;
+        wait_for(&input(104)).await;
+        stop.cancel();
+    };
+    let (follow, ()) = tokio::join!(run_until(cli.args, stop.clone()), chain);
+    follow.expect("follow mode ends cleanly");
+    assert_eq!(calls.head_unavailable.load(Ordering::Relaxed), 0);
+}
@@ -2,8 +2,9 @@ mod client;
 
 pub use client::{create_client, Client};
 pub use input_core::{
-    generate_hints_to_file, generate_hints_to_socket, parse_header, BlockStats, ExecutionClient,
-    RpcConfig,
+    generate_hints_to_file, generate_hints_to_socket, parse_header, stdin_slices, BlockStats,
+    ExecutionClient, HintsTimeout, InputFileName, InputMetadata, RetryPolicy, RpcConfig,
+    MIN_RATE_LIMIT,
 };
 
 #[cfg(feature = "ethrex")]
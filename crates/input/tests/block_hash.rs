@@ -0,0 +1,50 @@
+//! Block hashes across clients, on the `mainnet_25229957` fixtures both the
+//! reth and the ethrex guest ship with: the clients must read the same block
+//! hash off their own input formats, since `host differential` compares
+//! committed hashes against it.
+#![cfg(all(feature = "reth", feature = "ethrex"))]
+
+use input::{create_client, Client};
+
+const RETH_INPUT: &[u8] = include_bytes!(
+    "../../../bin/guests/stateless-validator-reth/inputs/mainnet_25229957_107_9_zec_reth.bin"
+);
+const ETHREX_INPUT: &[u8] = include_bytes!(
+    "../../../bin/guests/stateless-validator-ethrex/inputs/mainnet_25229957_107_9_zec_ethrex.bin"
+);
+
+#[test]
+fn reth_and_ethrex_inputs_carry_the_same_block_hash() {
+    let reth = create_client(Client::Reth)
+        .input_block_hash(RETH_INPUT)
+        .expect("reth block hash");
+    let ethrex = create_client(Client::Ethrex)
+        .input_block_hash(ETHREX_INPUT)
+        .expect("ethrex block hash");
+    assert_eq!(reth, ethrex);
+    assert_ne!(reth, [0; 32]);
+}
+
+#[test]
+fn reth_and_ethrex_inputs_describe_the_same_block() {
+    for (client, input) in [(Client::Reth, RETH_INPUT), (Client::Ethrex, ETHREX_INPUT)] {
+        let metadata = create_client(client)
+            .input_metadata(input)
+            .expect("metadata");
+        assert_eq!(metadata.block_number, 25229957, "{client:?}");
+        assert_eq!(metadata.tx_count, 107, "{client:?}");
+        assert_eq!(metadata.chain_name, Some("Mainnet"), "{client:?}");
+    }
+}
+
+#[test]
+fn reth_decodes_committed_publics() {
+    // bincode writes the committed `B256` as a length-prefixed byte string.
+    let hash: [u8; 32] = std::array::from_fn(|i| i as u8);
+    let mut publics = vec![32];
+    publics.extend_from_slice(&hash);
+    let reth = create_client(Client::Reth);
+    assert_eq!(reth.committed_block_hash(&publics).expect("hash"), hash);
+    assert!(reth.committed_block_hash(&publics[..20]).is_err());
+    assert!(reth.committed_block_hash(&[]).is_err());
+}
@@ -52,6 +52,10 @@ def load_benchmark_results(metrics_folder: Path) -> list[BenchmarkResult]:
         try:
             with open(json_file) as f:
                 data = json.load(f)
+
+            # Skip run summaries and proof publics, which are not per-input results
+            if "metrics" not in data:
+                continue
             
             opcode = extract_opcode_from_path(json_file)
             